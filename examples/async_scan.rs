@@ -1,7 +1,7 @@
 use crazyradio::{Channel, Crazyradio, SharedCrazyradio};
 
 #[tokio::main]
-async fn main() -> Result<(), crazyradio::Error> {
+async fn main() -> crazyradio::Result<()> {
     let radio = Crazyradio::open_first_async().await?;
     let cr = SharedCrazyradio::new(radio);
 
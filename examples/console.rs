@@ -1,7 +1,7 @@
 use crazyradio::{Channel, Crazyradio, Datarate};
 use std::str;
 
-fn main() -> Result<(), crazyradio::Error> {
+fn main() -> crazyradio::Result<()> {
     let mut cr = Crazyradio::open_first()?;
 
     cr.set_datarate(Datarate::Dr2M)?;
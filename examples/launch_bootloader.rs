@@ -1,4 +1,4 @@
-fn main() -> Result<(), crazyradio::Error> {
+fn main() -> crazyradio::Result<()> {
     let cr = crazyradio::Crazyradio::open_first()?;
 
     cr.launch_bootloader()?;
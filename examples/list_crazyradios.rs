@@ -1,4 +1,4 @@
-fn main() -> Result<(), crazyradio::Error> {
+fn main() -> crazyradio::Result<()> {
     let serials = crazyradio::Crazyradio::list_serials()?;
 
     println!("{} Crazyradio found:", serials.len());
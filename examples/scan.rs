@@ -1,6 +1,6 @@
 use crazyradio::{Channel, Crazyradio};
 
-fn main() -> Result<(), crazyradio::Error> {
+fn main() -> crazyradio::Result<()> {
     let mut cr = Crazyradio::open_first()?;
 
     println!("Opened Crazyradio with serial number: {}", cr.serial()?);
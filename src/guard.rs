@@ -0,0 +1,104 @@
+#[cfg(feature = "rusb")]
+use crate::RusbTransport;
+use crate::{Channel, Crazyradio, Datarate, Power, Result, UsbTransport};
+use std::ops::{Deref, DerefMut};
+
+// Setting value to restore when the guard is dropped
+enum PreviousSetting {
+    Channel(Channel),
+    Datarate(Datarate),
+    Power(Power),
+}
+
+/// Guard restoring a radio setting when dropped
+///
+/// Returned by [Crazyradio::with_channel()], [Crazyradio::with_datarate()]
+/// and [Crazyradio::with_power()]. The radio is accessible through the guard
+/// while it is alive. Errors while restoring the setting are ignored.
+///
+/// Example:
+/// ```no_run
+/// # use crazyradio::{Crazyradio, Channel};
+/// # #[cfg(feature = "rusb")]
+/// # fn main() -> Result<(), crazyradio::Error> {
+/// let mut cr = Crazyradio::open_first()?;
+/// {
+///     let mut cr = cr.with_channel(Channel::from_number(80)?)?;
+///     let mut ack_data = [0u8; 32];
+///     cr.send_packet(&[0xff], &mut ack_data)?;
+/// } // The previous channel is set back here
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "rusb"))]
+/// # fn main() {}
+/// ```
+pub struct SettingGuard<
+    'a,
+    #[cfg(feature = "rusb")] T: UsbTransport = RusbTransport,
+    #[cfg(not(feature = "rusb"))] T: UsbTransport,
+> {
+    radio: &'a mut Crazyradio<T>,
+    previous: PreviousSetting,
+}
+
+impl<T: UsbTransport> Crazyradio<T> {
+    /// Set the radio channel until the returned guard is dropped
+    ///
+    /// See [SettingGuard].
+    pub fn with_channel(&mut self, channel: Channel) -> Result<SettingGuard<'_, T>> {
+        let previous = PreviousSetting::Channel(self.channel());
+        self.set_channel(channel)?;
+        Ok(SettingGuard {
+            radio: self,
+            previous,
+        })
+    }
+
+    /// Set the datarate until the returned guard is dropped
+    ///
+    /// See [SettingGuard].
+    pub fn with_datarate(&mut self, datarate: Datarate) -> Result<SettingGuard<'_, T>> {
+        let previous = PreviousSetting::Datarate(self.datarate());
+        self.set_datarate(datarate)?;
+        Ok(SettingGuard {
+            radio: self,
+            previous,
+        })
+    }
+
+    /// Set the transmit power until the returned guard is dropped
+    ///
+    /// See [SettingGuard].
+    pub fn with_power(&mut self, power: Power) -> Result<SettingGuard<'_, T>> {
+        let previous = PreviousSetting::Power(self.power());
+        self.set_power(power)?;
+        Ok(SettingGuard {
+            radio: self,
+            previous,
+        })
+    }
+}
+
+impl<T: UsbTransport> Deref for SettingGuard<'_, T> {
+    type Target = Crazyradio<T>;
+
+    fn deref(&self) -> &Self::Target {
+        self.radio
+    }
+}
+
+impl<T: UsbTransport> DerefMut for SettingGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.radio
+    }
+}
+
+impl<T: UsbTransport> Drop for SettingGuard<'_, T> {
+    fn drop(&mut self) {
+        let _ = match self.previous {
+            PreviousSetting::Channel(channel) => self.radio.set_channel(channel),
+            PreviousSetting::Datarate(datarate) => self.radio.set_datarate(datarate),
+            PreviousSetting::Power(power) => self.radio.set_power(power),
+        };
+    }
+}
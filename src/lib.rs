@@ -6,44 +6,105 @@
 //! USB Dongle.
 //!
 //! Available Cargo features:
+//!  - **rusb** enables the [RusbTransport] and the functions to list and open the dongles with [rusb](https://crates.io/crates/rusb)
 //!  - **shared_radio** enables [SharedCrazyradio] object that allows to share a radio between threads
 //!  - **async** enables async function to create a [Crazyradio] object and use the [SharedCrazyradio]
-//!  - **serde** emables [serde](https://crates.io/crates/serde) serialization/deserialization of the [Channel] struct
+//!  - **serde_support** emables [serde](https://crates.io/crates/serde) serialization/deserialization of the [Channel] struct and of the [RadioLink]
+//!  - **experimental** enables functions sending raw vendor requests, intended for firmware development
+//!
+//! Only **rusb** is enabled by default. Without the **shared_radio** and
+//! **async** features the crate does not pull any channel or async runtime
+//! dependency. Without **rusb** a [Crazyradio] can only be created from
+//! another [UsbTransport] implementation with [Crazyradio::from_transport()],
+//! and its transport type parameter has no default.
+//!
+//! The crate requires `std`, also without **rusb**: the radio functions
+//! allocate, measure time with `std::time::Instant` and the shared radio
+//! uses threads. It is not usable on `no_std` targets.
 
 #[cfg(feature = "shared_radio")]
 mod shared_radio;
 #[cfg(feature = "shared_radio")]
 pub use crate::shared_radio::SharedCrazyradio;
 
+mod guard;
+pub use crate::guard::SettingGuard;
+
+mod transport;
+#[cfg(feature = "rusb")]
+pub use crate::transport::RusbTransport;
+pub use crate::transport::{DryRunTransport, UsbTransport};
+
+mod uri;
+pub use crate::uri::{Address, RadioLink, RadioUri};
+
 use core::time::Duration;
 #[cfg(feature = "serde_support")]
 use serde::{Deserialize, Serialize};
-
-type Result<T> = std::result::Result<T, Error>;
-
+use std::time::Instant;
+
+/// Result type returned by the functions of this crate
+pub type Result<T> = std::result::Result<T, Error>;
+
+// Used by the code generic over the transport, exposed as Crazyradio::BROADCAST_ADDRESS,
+// Crazyradio::MAX_PACKET_LEN and Crazyradio::MAX_ACK_LEN
+const BROADCAST_ADDRESS: [u8; 5] = [0xff, 0xe7, 0xe7, 0xe7, 0xe7];
+const MAX_PACKET_LEN: usize = 32;
+const MAX_ACK_LEN: usize = 32;
+
+#[cfg(feature = "rusb")]
+const CRAZYRADIO_VID: u16 = 0x1915;
+#[cfg(feature = "rusb")]
+const CRAZYRADIO_PID: u16 = 0x7777;
+// PID of the nRF24LU1 bootloader the Crazyradio enters with launch_bootloader()
+#[cfg(feature = "rusb")]
+const BOOTLOADER_PID: u16 = 0x0101;
+
+// Enumeration functions only open the devices to read the serial number and
+// never claim the interface, so they do not disturb a program using the radio.
+#[cfg(feature = "rusb")]
 fn find_crazyradio(
     nth: Option<usize>,
     serial: Option<&str>,
 ) -> Result<rusb::Device<rusb::GlobalContext>> {
-    let mut n = 0;
+    for (n, device) in list_crazyradio_devices()?.into_iter().enumerate() {
+        if nth.is_some() && nth != Some(n) {
+            continue;
+        }
+
+        // Radios whose serial cannot be read are skipped, as when listing the serials
+        if serial.is_some() && read_serial(&device).as_deref() != serial {
+            continue;
+        }
+
+        return Ok(device);
+    }
+    Err(Error::NotFound)
+}
+
+#[cfg(feature = "rusb")]
+fn list_crazyradio_devices() -> Result<Vec<rusb::Device<rusb::GlobalContext>>> {
+    list_usb_devices(CRAZYRADIO_VID, CRAZYRADIO_PID)
+}
+
+#[cfg(feature = "rusb")]
+fn list_usb_devices(
+    vendor_id: u16,
+    product_id: u16,
+) -> Result<Vec<rusb::Device<rusb::GlobalContext>>> {
+    let mut devices = vec![];
 
     for device in rusb::devices()?.iter() {
         let device_desc = device.device_descriptor()?;
 
-        if device_desc.vendor_id() == 0x1915 && device_desc.product_id() == 0x7777 {
-            let handle = device.open()?;
-
-            if (nth == None || nth == Some(n))
-                && (serial == None || serial == Some(&get_serial(&device_desc, &handle)?))
-            {
-                return Ok(device);
-            }
-            n += 1;
+        if device_desc.vendor_id() == vendor_id && device_desc.product_id() == product_id {
+            devices.push(device);
         }
     }
-    Err(Error::NotFound)
+    Ok(devices)
 }
 
+#[cfg(feature = "rusb")]
 fn get_serial<T: rusb::UsbContext>(
     device_desc: &rusb::DeviceDescriptor,
     handle: &rusb::DeviceHandle<T>,
@@ -59,28 +120,86 @@ fn get_serial<T: rusb::UsbContext>(
     }
 }
 
+// Best-effort: the device cannot be opened if it has no usable driver or if access is denied
+#[cfg(feature = "rusb")]
+fn read_serial(device: &rusb::Device<rusb::GlobalContext>) -> Option<String> {
+    let device_desc = device.device_descriptor().ok()?;
+    let handle = device.open().ok()?;
+    get_serial(&device_desc, &handle).ok()
+}
+
+#[cfg(feature = "rusb")]
+fn firmware_version(device_desc: &rusb::DeviceDescriptor) -> (u8, u8, u8) {
+    let version = device_desc.device_version();
+    (version.major(), version.minor(), version.sub_minor())
+}
+
+#[cfg(feature = "rusb")]
+fn get_radio_info(device: &rusb::Device<rusb::GlobalContext>) -> Result<RadioInfo> {
+    let device_desc = device.device_descriptor()?;
+
+    Ok(RadioInfo {
+        serial: read_serial(device),
+        firmware_version: firmware_version(&device_desc),
+        bus_number: device.bus_number(),
+        address: device.address(),
+        port_numbers: device.port_numbers()?,
+    })
+}
+
+#[cfg(feature = "rusb")]
 fn list_crazyradio_serials() -> Result<Vec<String>> {
     let mut serials = vec![];
 
-    for device in rusb::devices()?.iter() {
-        let device_desc = device.device_descriptor()?;
+    for device in list_crazyradio_devices()? {
+        if let Some(serial) = read_serial(&device) {
+            serials.push(serial);
+        }
+    }
+    Ok(serials)
+}
 
-        if device_desc.vendor_id() == 0x1915 && device_desc.product_id() == 0x7777 {
-            let handle: rusb::DeviceHandle<rusb::GlobalContext> = device.open()?;
+// Serials present more than once with their count, in order of first appearance
+#[cfg(feature = "rusb")]
+fn duplicated_serials(serials: Vec<String>) -> Vec<(String, usize)> {
+    let mut counts: Vec<(String, usize)> = vec![];
 
-            let languages = handle.read_languages(Duration::from_secs(1))?;
+    for serial in serials {
+        match counts.iter_mut().find(|(s, _)| *s == serial) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((serial, 1)),
+        }
+    }
 
-            if !languages.is_empty() {
-                let serial = handle.read_serial_number_string(
-                    languages[0],
-                    &device_desc,
-                    Duration::from_secs(1),
-                )?;
-                serials.push(serial);
-            }
+    counts.retain(|(_, count)| *count > 1);
+    counts
+}
+
+// Port numbers of the hub a device is connected to, empty for a root hub
+#[cfg(feature = "rusb")]
+fn hub_port_numbers(device: &RadioInfo) -> &[u8] {
+    device
+        .port_numbers
+        .split_last()
+        .map_or(&[], |(_, hub_ports)| hub_ports)
+}
+
+#[cfg(feature = "rusb")]
+fn group_by_hub(devices: Vec<RadioInfo>) -> Vec<Vec<RadioInfo>> {
+    let mut groups: Vec<Vec<RadioInfo>> = vec![];
+
+    for device in devices {
+        let group = groups.iter_mut().find(|group| {
+            group[0].bus_number == device.bus_number
+                && hub_port_numbers(&group[0]) == hub_port_numbers(&device)
+        });
+
+        match group {
+            Some(group) => group.push(device),
+            None => groups.push(vec![device]),
         }
     }
-    Ok(serials)
+    groups
 }
 
 enum UsbCommand {
@@ -96,15 +215,47 @@ enum UsbCommand {
     LaunchBootloader = 0xff,
 }
 
+// Compute the value of the ARD USB command from a delay
+fn ard_from_time(delay: Duration) -> Result<u16> {
+    if delay <= Duration::from_micros(4000) {
+        // Set to step above or equal to `delay`
+        let steps = (delay.as_micros() as u16).div_ceil(250);
+        Ok(steps.saturating_sub(1))
+    } else {
+        Err(Error::InvalidArgument)
+    }
+}
+
+// Rank of an ack when looking for the best channel or datarate, lower is
+// better: fewer retries, then a set power detector on equal retries
+fn ack_rank(ack: &Ack) -> (usize, bool) {
+    (ack.retry, !ack.power_detector)
+}
+
+// ARD fitting the longest ack at `datarate`, including the nRF24 settling time
+fn auto_ard_time(datarate: Datarate) -> Duration {
+    datarate.airtime(MAX_ACK_LEN, 5) + Duration::from_micros(130)
+}
+
 /// Represents a Crazyradio
 ///
 /// Holds the USB connection to a Crazyradio dongle.
 /// The connection is closed when this object goes out of scope.Crazyradio
 ///
+/// The Crazyradio firmware uses the nRF24 as a primary transmitter (PTX):
+/// data is received from devices only as ack payloads of the packets sent.
+/// The firmware has no USB request to configure the nRF24 as a receiver or to
+/// set the addresses of its RX pipes.
+///
+/// The USB communication goes through a [UsbTransport], by default the
+/// rusb-based [RusbTransport] created by the open functions. Another USB
+/// host stack can be used with [Crazyradio::from_transport()].
+///
 /// Usage example:
 /// ```no_run
 /// use crazyradio::{Crazyradio, Error, Channel};
 ///
+/// # #[cfg(feature = "rusb")]
 /// fn main() -> Result<(), Error> {
 ///     let mut cr = Crazyradio::open_first()?;   // Open the first detected dongle
 ///
@@ -112,7 +263,7 @@ enum UsbCommand {
 ///     cr.set_channel(Channel::from_number(42).unwrap());
 ///
 ///     // Send a `null` packet
-///     let mut ack_data = [0u8; 32];
+///     let mut ack_data = [0u8; Crazyradio::MAX_ACK_LEN];
 ///     let ack = cr.send_packet(&[0xff], &mut ack_data)?;
 ///
 ///     println!("Ack received: {}, length: {}, data: {:?}", ack.received,
@@ -121,20 +272,68 @@ enum UsbCommand {
 ///
 ///     Ok(())
 /// }
+/// # #[cfg(not(feature = "rusb"))]
+/// # fn main() {}
 /// ```
-pub struct Crazyradio {
-    device_desciptor: rusb::DeviceDescriptor,
-    device_handle: rusb::DeviceHandle<rusb::GlobalContext>,
+pub struct Crazyradio<
+    #[cfg(feature = "rusb")] T: UsbTransport = RusbTransport,
+    #[cfg(not(feature = "rusb"))] T: UsbTransport,
+> {
+    transport: T,
 
     cache_settings: bool,
+    cache_stats: CacheStats,
+
+    // USB timeouts
+    control_timeout: Duration,
+    transfer_timeout: Duration,
 
     // Settings cache
     channel: Channel,
     address: [u8; 5],
     datarate: Datarate,
+
+    // Other settings, only kept to be able to save and restore the radio state
+    power: Power,
+    arc: usize,
+    ard: u16,
+    ack_enable: bool,
+    cont_carrier: bool,
+
+    // Program the ARD when the datarate changes
+    auto_ard: bool,
+
+    // Link status
+    consecutive_lost: usize,
+    link_down_threshold: usize,
+    link_stats: LinkStats,
+    auto_reset_stats: bool,
+
+    // Ack payload length check
+    expected_ack_length: Option<usize>,
+
+    // Status of a packet sent that has not been read, the transfer timed out
+    status_pending: bool,
 }
 
+#[cfg(feature = "rusb")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rusb")))]
 impl Crazyradio {
+    /// Address conventionally used by the Crazyflies for broadcast packets
+    ///
+    /// Written `FFE7E7E7E7` in a radio URI. Used by
+    /// [Crazyradio::broadcast_to_default()].
+    pub const BROADCAST_ADDRESS: [u8; 5] = BROADCAST_ADDRESS;
+
+    /// Maximum length of the data of a packet sent, in bytes
+    pub const MAX_PACKET_LEN: usize = MAX_PACKET_LEN;
+
+    /// Maximum length of an ack payload, in bytes
+    ///
+    /// Ack data buffers of this size receive the full ack payload, see
+    /// [Crazyradio::send_packet()].
+    pub const MAX_ACK_LEN: usize = MAX_ACK_LEN;
+
     /// Open the first Crazyradio detected and returns a Crazyradio object.
     ///
     /// The dongle is reset to boot values before being returned
@@ -142,6 +341,42 @@ impl Crazyradio {
         Crazyradio::open_nth(0)
     }
 
+    /// Open the first Crazyradio detected, giving up after `timeout`.
+    ///
+    /// Works like [Crazyradio::open_first()] but returns [Error::Timeout] if
+    /// the radio is not opened within `timeout`, for example because a
+    /// misbehaving dongle does not answer USB requests.
+    ///
+    /// The radio is opened in a thread. On timeout the thread is left running
+    /// until the USB requests return, the radio is then closed.
+    pub fn open_first_timeout(timeout: Duration) -> Result<Self> {
+        let (tx, rx) = std::sync::mpsc::channel();
+
+        std::thread::spawn(move || {
+            let _ = tx.send(Self::open_first());
+        });
+
+        rx.recv_timeout(timeout).unwrap_or(Err(Error::Timeout))
+    }
+
+    /// Open the first Crazyradio detected and apply a configuration.
+    ///
+    /// The dongle is reset to boot values, then `config` is applied. Same as
+    /// calling [Crazyradio::open()] with [OpenOptions::config()].
+    ///
+    /// Example:
+    /// ```no_run
+    /// use crazyradio::{Crazyradio, RadioConfig};
+    /// # fn main() -> Result<(), crazyradio::Error> {
+    /// let config = RadioConfig::default();
+    /// let mut cr = Crazyradio::open_first_with_config(&config)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn open_first_with_config(config: &RadioConfig) -> Result<Self> {
+        Self::open(OpenOptions::new().config(*config))
+    }
+
     /// Open the nth Crazyradio detected and returns a Crazyradio object.
     ///
     /// Radios are ordered appearance in the USB device list. This order is
@@ -149,11 +384,14 @@ impl Crazyradio {
     ///
     /// The dongle is reset to boot values before being returned
     pub fn open_nth(nth: usize) -> Result<Self> {
-        Self::open_generic(Some(nth), None)
+        Self::open(OpenOptions::new().nth(nth))
     }
 
     /// Open a Crazyradio by specifying its serial number
     ///
+    /// If multiple radios have the same serial number, the first one found is
+    /// opened, see [Crazyradio::check_serial_uniqueness()].
+    ///
     /// Example:
     /// ```no_run
     /// use crazyradio::Crazyradio;
@@ -163,20 +401,209 @@ impl Crazyradio {
     /// # }
     /// ```
     pub fn open_by_serial(serial: &str) -> Result<Self> {
-        Self::open_generic(None, Some(serial))
+        Self::open(OpenOptions::new().serial(serial))
+    }
+
+    /// Wait for a Crazyradio with the given serial number to be connected and open it
+    ///
+    /// The USB devices are enumerated every 100ms until the radio is found,
+    /// which allows to open a radio that has just been plugged in or powered
+    /// on. Returns [Error::Timeout] if the radio is not found within `timeout`.
+    /// Other errors while opening the radio are returned immediately.
+    pub fn wait_for_serial(serial: &str, timeout: Duration) -> Result<Self> {
+        let start = Instant::now();
+
+        loop {
+            match Self::open_by_serial(serial) {
+                Err(Error::NotFound) if start.elapsed() < timeout => {
+                    std::thread::sleep(Duration::from_millis(100))
+                }
+                Err(Error::NotFound) => return Err(Error::Timeout),
+                result => return result,
+            }
+        }
+    }
+
+    /// Reopen a Crazyradio by serial number and apply a configuration
+    ///
+    /// Intended to recover a radio that has been disconnected: the radio is
+    /// opened with [Crazyradio::open_by_serial()] and `config` is applied.
+    /// On failure the attempt is retried after the delay given by `backoff`.
+    /// The error of the last attempt is returned if all attempts fail.
+    pub fn reconnect(serial: &str, config: &RadioConfig, backoff: Backoff) -> Result<Self> {
+        let mut attempt = 0;
+
+        loop {
+            let result = Self::open_by_serial(serial).and_then(|mut cr| {
+                cr.apply_config(config)?;
+                Ok(cr)
+            });
+
+            match result {
+                Err(_) if attempt + 1 < backoff.attempts => {
+                    std::thread::sleep(backoff.delay(attempt));
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Open the nth Crazyradio among the ones for which `predicate` returns true
+    ///
+    /// The predicate is called with the [RadioInfo] of each connected
+    /// Crazyradio, in the same order as [Crazyradio::list_devices()]. Only the
+    /// selected radio is claimed and reset to boot values. Radios that cannot
+    /// be opened are passed with a `None` serial number, radios whose
+    /// information cannot be read at all are skipped.
+    ///
+    /// Example:
+    /// ```no_run
+    /// use crazyradio::Crazyradio;
+    /// # fn main() -> Result<(), crazyradio::Error> {
+    /// // Open the second radio with firmware version 0.53 or newer
+    /// let mut cr = Crazyradio::open_nth_matching(1, |info| info.firmware_version >= (0, 5, 3))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn open_nth_matching(nth: usize, predicate: impl Fn(&RadioInfo) -> bool) -> Result<Self> {
+        let mut n = 0;
+
+        for device in list_crazyradio_devices()? {
+            let info = match get_radio_info(&device) {
+                Ok(info) => info,
+                Err(_) => continue,
+            };
+
+            if predicate(&info) {
+                if n == nth {
+                    return Self::open_device(device, &OpenOptions::new());
+                }
+                n += 1;
+            }
+        }
+        Err(Error::NotFound)
+    }
+
+    /// Open the first Crazyradio for which `predicate` returns true and that can be opened
+    ///
+    /// Works like [Crazyradio::open_nth_matching()] with `nth` at 0, but if a
+    /// matching radio cannot be opened, for example because it is used by
+    /// another program, the next matching radio is tried. Returns the error
+    /// of the last radio tried if none could be opened, or [Error::NotFound]
+    /// if no radio matches. Radios whose information cannot be read are
+    /// skipped.
+    ///
+    /// A radio used by another program can usually still be read, its serial
+    /// number included, so it may match. If claiming its interface fails the
+    /// next matching radio is tried, otherwise the radio is returned and the
+    /// failure only shows up when it is used.
+    ///
+    /// Example, opening the first radio connected to the USB bus 1 that can
+    /// be claimed:
+    /// ```no_run
+    /// use crazyradio::Crazyradio;
+    ///
+    /// # fn main() -> Result<(), crazyradio::Error> {
+    /// // If the interface of the first radio of the bus is claimed, the second one is opened
+    /// let mut cr = Crazyradio::open_first_where(|info| info.bus_number == 1)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn open_first_where(predicate: impl Fn(&RadioInfo) -> bool) -> Result<Self> {
+        let mut result = Err(Error::NotFound);
+
+        for device in list_crazyradio_devices()? {
+            let info = match get_radio_info(&device) {
+                Ok(info) => info,
+                Err(_) => continue,
+            };
+
+            if predicate(&info) {
+                result = Self::open_device(device, &OpenOptions::new());
+                if result.is_ok() {
+                    break;
+                }
+            }
+        }
+        result
+    }
+
+    /// Open a Crazyradio from an identifier previously returned by [Crazyradio::id()]
+    ///
+    /// The dongle is looked up by serial number, this allows to find the
+    /// same physical dongle after it has been unplugged and plugged back,
+    /// possibly in another USB port.
+    pub fn open_by_id(id: &RadioId) -> Result<Self> {
+        Self::open_by_serial(&id.serial)
+    }
+
+    /// Open the Crazyradio designated by a radio URI and configure it for the URI link
+    ///
+    /// The nth Crazyradio is opened, n being the dongle index of the URI, and
+    /// the channel, datarate and address of the URI are set. See [RadioUri]
+    /// for the URI format.
+    ///
+    /// Example:
+    /// ```no_run
+    /// use crazyradio::Crazyradio;
+    /// # fn main() -> Result<(), crazyradio::Error> {
+    /// let mut cr = Crazyradio::open_uri("radio://0/80/2M/E7E7E7E7E7")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn open_uri(uri: &str) -> Result<Self> {
+        let uri: RadioUri = uri.parse()?;
+
+        let mut cr = Self::open_nth(uri.dongle_index())?;
+        cr.configure_from_uri(&uri)?;
+        Ok(cr)
+    }
+
+    /// Open a Crazyradio using the provided [OpenOptions]
+    ///
+    /// This is the generic version of the open function, the other open_*
+    /// functions are calling it with reset enabled.
+    ///
+    /// Example:
+    /// ```no_run
+    /// use crazyradio::{Crazyradio, OpenOptions};
+    /// # fn main() -> Result<(), crazyradio::Error> {
+    /// let mut cr = Crazyradio::open(OpenOptions::new().serial("FD61E54B7A").reset(false))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn open(options: OpenOptions) -> Result<Self> {
+        let device = find_crazyradio(options.nth, options.serial.as_deref())?;
+
+        Self::open_device(device, &options)
     }
 
-    // Generic version of the open function, called by the other open_* functions
-    fn open_generic(nth: Option<usize>, serial: Option<&str>) -> Result<Self> {
-        let device = find_crazyradio(nth, serial)?;
+    /// Open a Crazyradio from a USB device already enumerated by the caller
+    ///
+    /// Allows to integrate with an application that enumerates the USB
+    /// devices itself. Returns [Error::NotFound] if `device` is not a
+    /// Crazyradio. Like the other open functions, the interface is claimed
+    /// and the dongle is reset to boot values.
+    pub fn from_device(device: rusb::Device<rusb::GlobalContext>) -> Result<Self> {
+        let device_desc = device.device_descriptor()?;
+
+        if device_desc.vendor_id() != CRAZYRADIO_VID || device_desc.product_id() != CRAZYRADIO_PID {
+            return Err(Error::NotFound);
+        }
 
-        let device_desciptor = device.device_descriptor()?;
-        let mut device_handle = device.open()?;
+        Self::open_device(device, &OpenOptions::new())
+    }
 
-        device_handle.claim_interface(0)?;
+    // Open and initialize a device already identified as a Crazyradio
+    fn open_device(
+        device: rusb::Device<rusb::GlobalContext>,
+        options: &OpenOptions,
+    ) -> Result<Self> {
+        let transport = RusbTransport::open(device, options.detach_kernel_driver)?;
 
         // Make sure the dongle version is >= 0.5
-        let version = device_desciptor.device_version();
+        let version = transport.device_desciptor.device_version();
         let version = version.major() as f64
             + (version.minor() as f64 / 10.0)
             + (version.sub_minor() as f64 / 100.0);
@@ -184,427 +611,3304 @@ impl Crazyradio {
             return Err(Error::DongleVersionNotSupported);
         }
 
-        let mut cr = Crazyradio {
-            device_desciptor,
-            device_handle,
+        let mut cr = Crazyradio::new(transport);
 
-            cache_settings: true,
+        if options.reset {
+            cr.reset()?;
+        }
 
-            channel: Channel::from_number(2).unwrap(),
-            address: [0xe7; 5],
-            datarate: Datarate::Dr2M,
+        if let Some(config) = &options.config {
+            cr.with_cache_disabled(|cr| cr.apply_config(config))?;
+        }
+
+        Ok(cr)
+    }
+
+    /// Iterate over the connected Crazyradios, opening them one by one
+    ///
+    /// The list of Crazyradio is taken when calling this function but each
+    /// radio is only opened, and reset to boot values, when the iterator
+    /// advances. An error opening one radio does not stop the iteration.
+    ///
+    /// Example:
+    /// ```no_run
+    /// use crazyradio::Crazyradio;
+    ///
+    /// for radio in Crazyradio::iter() {
+    ///     match radio {
+    ///         Ok(radio) => println!("Opened {:?}", radio.serial()),
+    ///         Err(e) => println!("Cannot open radio: {}", e),
+    ///     }
+    /// }
+    /// ```
+    pub fn iter() -> impl Iterator<Item = Result<Self>> {
+        let devices = match list_crazyradio_devices() {
+            Ok(devices) => devices.into_iter().map(Ok).collect(),
+            Err(e) => vec![Err(e)],
         };
 
-        cr.reset()?;
+        devices
+            .into_iter()
+            .map(|device| device.and_then(|d| Self::open_device(d, &OpenOptions::new())))
+    }
 
-        Ok(cr)
+    /// Open each connected Crazyradio in turn and call `f` with it
+    ///
+    /// The radios are opened, and reset to boot values, one by one in the
+    /// same order as [Crazyradio::iter()]. Each radio is closed before the
+    /// next one is opened. Iteration stops at the first error, either while
+    /// opening a radio or returned by `f`, and this error is returned.
+    ///
+    /// Example:
+    /// ```no_run
+    /// use crazyradio::Crazyradio;
+    /// # fn main() -> Result<(), crazyradio::Error> {
+    /// Crazyradio::for_each(|cr| {
+    ///     println!("{:?}", cr.id()?);
+    ///     Ok(())
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn for_each(mut f: impl FnMut(&mut Crazyradio) -> Result<()>) -> Result<()> {
+        for radio in Self::iter() {
+            f(&mut radio?)?;
+        }
+        Ok(())
     }
 
     /// Return an ordered list of serial numbers of connected Crazyradios
     ///
     /// The order of the list is the same as accepted by the open_nth() function.
+    ///
+    /// The radios are opened to read their serial number but their interface
+    /// is not claimed: listing the radios does not disturb a program
+    /// currently using one of them. Radios whose serial number cannot be
+    /// read, for example because access to the device is denied, are not
+    /// listed.
     pub fn list_serials() -> Result<Vec<String>> {
         list_crazyradio_serials()
     }
 
-    /// Return the serial number of this radio
-    pub fn serial(&self) -> Result<String> {
-        get_serial(&self.device_desciptor, &self.device_handle)
-    }
-
-    /// Reset dongle parameters to boot values.
+    /// Return the serial numbers shared by multiple connected Crazyradios
     ///
-    /// This function is called by Crazyradio::open_*.
-    pub fn reset(&mut self) -> Result<()> {
-        let prev_cache_settings = self.cache_settings;
-        self.cache_settings = false;
-
-        self.set_datarate(Datarate::Dr2M)?;
-        self.set_channel(Channel::from_number(2).unwrap())?;
-        self.set_cont_carrier(false)?;
-        self.set_address(&[0xe7, 0xe7, 0xe7, 0xe7, 0xe7])?;
-        self.set_power(Power::P0dBm)?;
-        self.set_arc(3)?;
-        self.set_ard_bytes(32)?;
-        self.set_ack_enable(true)?;
-
-        self.cache_settings = prev_cache_settings;
-
-        Ok(())
+    /// Some dongles report the same serial number, [Crazyradio::open_by_serial()]
+    /// then opens the first one found. Each serial number appearing more
+    /// than once is returned with the number of radios reporting it. The
+    /// radios can then be selected by USB port instead, see
+    /// [Crazyradio::open_nth_matching()].
+    pub fn check_serial_uniqueness() -> Result<Vec<(String, usize)>> {
+        Ok(duplicated_serials(Self::list_serials()?))
     }
 
-    /// Enable or disable caching of settings
+    /// Return information about the connected Crazyradios
     ///
-    /// If enabled, setting the radio channel, address or datarate will be
-    /// ignored if the settings is the same as the one already set in the dongle
+    /// The order of the list is the same as accepted by the open_nth() function.
     ///
-    /// This is enabled by default and is a useful functionality to efficiently
-    /// implement communication to multiple device as changing these settings
-    /// require USB communication and is quite slow.
-    pub fn set_cache_settings(&mut self, cache_settings: bool) {
-        self.cache_settings = cache_settings;
+    /// Like [Crazyradio::list_serials()], this does not claim the radios
+    /// interface and does not disturb a program currently using one of them.
+    pub fn list_devices() -> Result<Vec<RadioInfo>> {
+        list_crazyradio_devices()?
+            .iter()
+            .map(get_radio_info)
+            .collect()
     }
 
-    /// Set the radio channel.
-    pub fn set_channel(&mut self, channel: Channel) -> Result<()> {
-        if !self.cache_settings || self.channel != channel {
-            self.device_handle.write_control(
-                0x40,
-                UsbCommand::SetRadioChannel as u8,
-                channel.0 as u16,
-                0,
-                &[],
-                Duration::from_secs(1),
-            )?;
-            self.channel = channel;
-        }
+    /// Return information about the Crazyradios currently in bootloader mode
+    ///
+    /// The Crazyradio uses a different USB product ID when it runs the
+    /// bootloader, after calling [Crazyradio::launch_bootloader()], and it
+    /// does not appear anymore in [Crazyradio::list_devices()]. The bootloader
+    /// might not report a serial number.
+    pub fn list_bootloaders() -> Result<Vec<RadioInfo>> {
+        list_usb_devices(CRAZYRADIO_VID, BOOTLOADER_PID)?
+            .iter()
+            .map(get_radio_info)
+            .collect()
+    }
 
-        Ok(())
+    /// Return information about the Crazyradios connected to a USB bus
+    ///
+    /// Works like [Crazyradio::list_devices()] but only returns the radios
+    /// whose [RadioInfo::bus_number] is `bus`. Radios connected to other buses
+    /// are not opened to read their serial number.
+    pub fn list_devices_on_bus(bus: u8) -> Result<Vec<RadioInfo>> {
+        list_crazyradio_devices()?
+            .iter()
+            .filter(|device| device.bus_number() == bus)
+            .map(get_radio_info)
+            .collect()
     }
 
-    /// Set the datarate.
-    pub fn set_datarate(&mut self, datarate: Datarate) -> Result<()> {
-        if !self.cache_settings || self.datarate != datarate {
-            self.device_handle.write_control(
-                0x40,
-                UsbCommand::SetDataRate as u8,
-                datarate as u16,
-                0,
-                &[],
-                Duration::from_secs(1),
-            )?;
-            self.datarate = datarate;
+    /// Return information about the connected Crazyradios and whether they can be opened
+    ///
+    /// Each Crazyradio is opened without reset: its interface is claimed,
+    /// its firmware version checked, and it is closed again. The result of
+    /// this attempt is returned alongside the radio information, for example
+    /// [Error::UsbError] with [rusb::Error::Busy] if the radio is used by
+    /// another program or [Error::DongleVersionNotSupported].
+    ///
+    /// The order of the list is the same as accepted by the open_nth()
+    /// function. The serial number is `None` if the radio cannot be opened.
+    /// Radios currently used by this program fail to open as well. If the
+    /// information of a radio cannot be read, the error is returned as its
+    /// result and only its bus number and address are filled in.
+    pub fn probe_all() -> Result<Vec<(RadioInfo, Result<()>)>> {
+        let mut result = vec![];
+
+        for device in list_crazyradio_devices()? {
+            let (info, usable) = match get_radio_info(&device) {
+                Ok(info) => {
+                    let options = OpenOptions::new().reset(false);
+                    (info, Self::open_device(device, &options).map(|_| ()))
+                }
+                Err(e) => {
+                    let info = RadioInfo {
+                        serial: None,
+                        firmware_version: (0, 0, 0),
+                        bus_number: device.bus_number(),
+                        address: device.address(),
+                        port_numbers: vec![],
+                    };
+                    (info, Err(e))
+                }
+            };
+
+            result.push((info, usable));
         }
 
-        Ok(())
+        Ok(result)
     }
 
-    /// Set the radio address.
-    pub fn set_address(&mut self, address: &[u8; 5]) -> Result<()> {
-        if !self.cache_settings || self.address != *address {
-            self.device_handle.write_control(
-                0x40,
-                UsbCommand::SetRadioAddress as u8,
-                0,
-                0,
-                address,
-                Duration::from_secs(1),
-            )?;
-            self.address.copy_from_slice(address);
-        }
+    /// Return information about the connected Crazyradios grouped by USB hub
+    ///
+    /// Radios connected to the same hub port, as identified by their
+    /// [RadioInfo::bus_number] and their [RadioInfo::port_numbers] without
+    /// the last port, share the USB bandwidth of this hub. Radios connected
+    /// directly to a root hub are grouped by bus. Groups and radios in each
+    /// group are in the same order as [Crazyradio::list_devices()].
+    pub fn group_by_hub() -> Result<Vec<Vec<RadioInfo>>> {
+        Ok(group_by_hub(Self::list_devices()?))
+    }
 
-        Ok(())
+    /// Return the serial number of this radio
+    pub fn serial(&self) -> Result<String> {
+        self.transport.serial()
     }
 
-    /// Set the transmit power.
-    pub fn set_power(&mut self, power: Power) -> Result<()> {
-        self.device_handle.write_control(
-            0x40,
-            UsbCommand::SetRadioPower as u8,
-            power as u16,
-            0,
-            &[],
-            Duration::from_secs(1),
-        )?;
-        Ok(())
+    /// Return the USB product string of this radio
+    ///
+    /// The Crazyradio and the Crazyradio PA use the same USB IDs and the same
+    /// firmware protocol, and the firmware does not report whether the power
+    /// amplifier is present. The product string is the only descriptor that
+    /// may differ between variants, depending on how the firmware was built,
+    /// so it cannot be used to reliably detect a Crazyradio PA. The [Power]
+    /// levels are the nRF24 output power, before the amplifier.
+    pub fn product(&self) -> Result<String> {
+        self.transport.product()
     }
 
-    /// Set time to wait for the ack packet.
-    pub fn set_ard_time(&mut self, delay: Duration) -> Result<()> {
-        if delay <= Duration::from_millis(4000) {
-            // Set to step above or equal to `delay`
-            let ard = (delay.as_millis() as u16 / 250) - 1;
-            self.device_handle.write_control(
-                0x40,
-                UsbCommand::SetRadioArd as u8,
-                ard,
-                0,
-                &[],
-                Duration::from_secs(1),
-            )?;
+    /// Check that the USB strings of this radio are the ones of a Crazyradio
+    ///
+    /// Other devices can use the Crazyradio USB vendor and product IDs. This
+    /// checks that the manufacturer string is `Bitcraze AB` and that the
+    /// product string contains `Crazyradio`, and returns
+    /// [Error::NotACrazyradio] otherwise. The check is not done by the open
+    /// functions.
+    pub fn verify_identity(&self) -> Result<()> {
+        let manufacturer = self.transport.manufacturer()?;
+        let product = self.product()?;
+
+        if manufacturer == "Bitcraze AB" && product.contains("Crazyradio") {
             Ok(())
         } else {
-            Err(Error::InvalidArgument)
+            Err(Error::NotACrazyradio)
         }
     }
 
-    /// Set time to wait for the ack packet by specifying the max byte-length of the ack payload.
-    pub fn set_ard_bytes(&mut self, nbytes: u8) -> Result<()> {
-        if nbytes <= 32 {
-            self.device_handle.write_control(
-                0x40,
-                UsbCommand::SetRadioArd as u8,
-                0x80 | nbytes as u16,
-                0,
-                &[],
-                Duration::from_secs(1),
-            )?;
-            Ok(())
-        } else {
-            Err(Error::InvalidArgument)
-        }
+    /// Return an identifier for this radio
+    ///
+    /// The identifier can be stored and later passed to
+    /// [Crazyradio::open_by_id()] to open the same physical dongle again.
+    pub fn id(&self) -> Result<RadioId> {
+        Ok(RadioId {
+            serial: self.serial()?,
+            firmware_version: firmware_version(&self.transport.device_desciptor),
+        })
     }
 
-    /// Set the number of time the radio will retry to send the packet if an ack packet is not received in time.
-    pub fn set_arc(&mut self, arc: usize) -> Result<()> {
-        if arc <= 15 {
-            self.device_handle.write_control(
-                0x40,
-                UsbCommand::SetRadioArc as u8,
-                arc as u16,
-                0,
-                &[],
-                Duration::from_secs(1),
-            )?;
-            Ok(())
-        } else {
-            Err(Error::InvalidArgument)
-        }
+    /// Return the USB port numbers chain from the root hub to this radio
+    ///
+    /// See [RadioInfo::port_numbers].
+    pub fn port_path(&self) -> Result<Vec<u8>> {
+        Ok(self.transport.device_handle.device().port_numbers()?)
     }
 
-    /// Set if the radio waits for an ack packet.
+    /// Return the USB descriptors information of the radio interface
+    ///
+    /// The information is read from the active configuration descriptor of
+    /// the radio, for the interface used by this crate. It allows to check
+    /// that the dongle has the expected endpoints layout: a bulk OUT endpoint
+    /// 0x01 and a bulk IN endpoint 0x81.
+    pub fn interface_info(&self) -> Result<InterfaceInfo> {
+        let device = self.transport.device_handle.device();
+        let usb_version = self.transport.device_desciptor.usb_version();
+        let config = device.active_config_descriptor()?;
+
+        let interface = config
+            .interfaces()
+            .flat_map(|interface| interface.descriptors())
+            .find(|descriptor| descriptor.interface_number() == 0)
+            .ok_or(Error::NotFound)?;
+
+        Ok(InterfaceInfo {
+            usb_version: (
+                usb_version.major(),
+                usb_version.minor(),
+                usb_version.sub_minor(),
+            ),
+            interface_number: interface.interface_number(),
+            endpoints: interface
+                .endpoint_descriptors()
+                .map(|endpoint| EndpointInfo {
+                    address: endpoint.address(),
+                    max_packet_size: endpoint.max_packet_size(),
+                })
+                .collect(),
+        })
+    }
+
+    /// Return the USB speed at which the radio is connected
+    ///
+    /// The Crazyradio is a Full Speed device. A lower speed usually indicates
+    /// a problem with the cable or hub and explains a low throughput.
+    pub fn usb_speed(&self) -> rusb::Speed {
+        self.transport.device_handle.device().speed()
+    }
+}
+
+impl<T: UsbTransport> Crazyradio<T> {
+    /// Create a Crazyradio communicating through `transport`
+    ///
+    /// `transport` must be connected to a Crazyradio with its interface
+    /// claimed. The dongle is reset to boot values before being returned.
+    pub fn from_transport(transport: T) -> Result<Self> {
+        let mut cr = Crazyradio::new(transport);
+        cr.reset()?;
+        Ok(cr)
+    }
+
+    /// Return the transport used to communicate with the dongle
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Return the transport used to communicate with the dongle, mutably
+    ///
+    /// Communicating with the dongle directly through the transport bypasses
+    /// the settings cache.
+    pub fn transport_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    // Create the object with the settings cache at boot values
+    fn new(transport: T) -> Self {
+        Crazyradio {
+            transport,
+
+            cache_settings: true,
+            cache_stats: CacheStats::default(),
+
+            control_timeout: Duration::from_secs(1),
+            transfer_timeout: Duration::from_secs(1),
+
+            channel: Channel::from_number(2).unwrap(),
+            address: [0xe7; 5],
+            datarate: Datarate::Dr2M,
+
+            power: Power::P0dBm,
+            arc: 3,
+            ard: 0x80 | 32,
+            ack_enable: true,
+            cont_carrier: false,
+
+            auto_ard: false,
+
+            consecutive_lost: 0,
+            link_down_threshold: 100,
+            link_stats: LinkStats::default(),
+            auto_reset_stats: false,
+
+            expected_ack_length: None,
+
+            status_pending: false,
+        }
+    }
+
+    /// Reset dongle parameters to boot values.
+    ///
+    /// This function is called by Crazyradio::open_*.
+    pub fn reset(&mut self) -> Result<()> {
+        self.with_cache_disabled(|cr| {
+            cr.set_datarate(Datarate::Dr2M)?;
+            cr.set_channel(Channel::from_number(2).unwrap())?;
+            cr.set_cont_carrier(false)?;
+            cr.set_address([0xe7; 5])?;
+            cr.set_power(Power::P0dBm)?;
+            cr.set_arc(3)?;
+            cr.set_ard_bytes(32)?;
+            cr.set_ack_enable(true)?;
+
+            Ok(())
+        })
+    }
+
+    /// Reset the link parameters to boot values, keeping the power and carrier settings.
+    ///
+    /// Works like [Crazyradio::reset()] for the datarate, channel, address,
+    /// retries and ack settings, but the transmit power and the continuous
+    /// carrier mode are left untouched.
+    pub fn reset_link(&mut self) -> Result<()> {
+        self.with_cache_disabled(|cr| {
+            cr.set_datarate(Datarate::Dr2M)?;
+            cr.set_channel(Channel::from_number(2).unwrap())?;
+            cr.set_address([0xe7; 5])?;
+            cr.set_arc(3)?;
+            cr.set_ard_bytes(32)?;
+            cr.set_ack_enable(true)?;
+
+            Ok(())
+        })
+    }
+
+    /// Return a snapshot of the current radio settings
+    ///
+    /// The snapshot can be applied back later with [Crazyradio::restore()].
+    /// Like the getters, it contains the values last set and not values read
+    /// back from the dongle.
+    pub fn state(&self) -> RadioState {
+        RadioState {
+            channel: self.channel,
+            address: self.address,
+            datarate: self.datarate,
+            power: self.power,
+            arc: self.arc,
+            ard: self.ard,
+            ack_enable: self.ack_enable,
+        }
+    }
+
+    /// Reset dongle parameters to boot values and return the previous settings.
+    ///
+    /// Works like [Crazyradio::reset()]. The returned state can be passed
+    /// to [Crazyradio::restore()] to go back to the previous configuration, for
+    /// example after a scan.
+    pub fn reset_saving(&mut self) -> Result<RadioState> {
+        let state = self.state();
+        self.reset()?;
+        Ok(state)
+    }
+
+    /// Apply settings previously saved with [Crazyradio::state()] or [Crazyradio::reset_saving()]
+    ///
+    /// Channel, address and datarate are only sent to the dongle if they
+    /// differ from the current settings and caching is enabled.
+    pub fn restore(&mut self, state: &RadioState) -> Result<()> {
+        self.set_datarate(state.datarate)?;
+        self.set_channel(state.channel)?;
+        self.set_address(state.address)?;
+        self.set_power(state.power)?;
+        self.set_arc(state.arc)?;
+        self.set_ard(state.ard)?;
+        self.set_ack_enable(state.ack_enable)?;
+
+        Ok(())
+    }
+
+    /// Send all the current settings to the dongle again
+    ///
+    /// To be used when the dongle might have lost its configuration without
+    /// this object knowing it, for example if it has been reset by a USB
+    /// suspend/resume cycle when the computer went to sleep. The settings are
+    /// sent regardless of the caching configuration.
+    pub fn reapply_settings(&mut self) -> Result<()> {
+        let state = self.state();
+        self.with_cache_disabled(|cr| cr.restore(&state))
+    }
+
+    /// Put the radio in a safe idle state.
+    ///
+    /// Intended to be called between bursts of activity to make sure the
+    /// radio does not interfere with other devices. The idle state is:
+    ///  - Continuous carrier disabled
+    ///  - Lowest transmit power ([Power::Pm18dBm])
+    ///  - Ack enabled
+    ///
+    /// Channel, address and datarate are left untouched.
+    pub fn idle(&mut self) -> Result<()> {
+        self.set_cont_carrier(false)?;
+        self.set_power(Power::Pm18dBm)?;
+        self.set_ack_enable(true)?;
+
+        Ok(())
+    }
+
+    /// Enable or disable caching of settings
+    ///
+    /// If enabled, setting the radio channel, address or datarate will be
+    /// ignored if the settings is the same as the one already set in the dongle
+    ///
+    /// This is enabled by default and is a useful functionality to efficiently
+    /// implement communication to multiple device as changing these settings
+    /// require USB communication and is quite slow.
+    ///
+    /// The settings cannot be read back from the dongle, so the cache is only
+    /// valid as long as no one else changes the dongle configuration. Disable
+    /// caching if the dongle state might have changed, for example after it
+    /// has been opened without reset.
+    pub fn set_cache_settings(&mut self, cache_settings: bool) {
+        self.cache_settings = cache_settings;
+    }
+
+    /// Set the timeout of the USB control transfers
+    ///
+    /// Control transfers are used to configure the dongle, they are
+    /// answered directly by the dongle and should be fast. Defaults to 1 second.
+    pub fn set_control_timeout(&mut self, timeout: Duration) {
+        self.control_timeout = timeout;
+    }
+
+    /// Set the timeout of the USB bulk transfers
+    ///
+    /// Bulk transfers are used to send packets and receive acks. Their duration
+    /// depends on the radio link: datarate, number of retries and ack wait time.
+    /// Defaults to 1 second.
+    pub fn set_transfer_timeout(&mut self, timeout: Duration) {
+        self.transfer_timeout = timeout;
+    }
+
+    /// Return how many times the cached settings have been sent or skipped
+    ///
+    /// Counted since the radio has been opened or since the last call to
+    /// [Crazyradio::reset_cache_stats()]. Allows to check how efficient the
+    /// settings cache is, for example when communicating with multiple devices.
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache_stats
+    }
+
+    /// Reset the settings cache statistics
+    pub fn reset_cache_stats(&mut self) {
+        self.cache_stats = CacheStats::default();
+    }
+
+    /// Run `f` with settings caching disabled
+    ///
+    /// All settings set by `f` are sent to the dongle. The previous caching
+    /// state is restored when `f` returns, even if it returns an error.
+    ///
+    /// Example:
+    /// ```no_run
+    /// # use crazyradio::{Crazyradio, Channel};
+    /// # #[cfg(feature = "rusb")]
+    /// # fn main() -> Result<(), crazyradio::Error> {
+    /// # let mut cr = Crazyradio::open_first()?;
+    /// cr.with_cache_disabled(|cr| cr.set_channel(Channel::from_number(42)?))?;
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "rusb"))]
+    /// # fn main() {}
+    /// ```
+    pub fn with_cache_disabled<R>(&mut self, f: impl FnOnce(&mut Self) -> Result<R>) -> Result<R> {
+        let prev_cache_settings = self.cache_settings;
+        self.cache_settings = false;
+
+        let result = f(self);
+
+        self.cache_settings = prev_cache_settings;
+
+        result
+    }
+
+    /// Return the radio channel last set.
+    ///
+    /// The Crazyradio firmware does not allow to read settings back from the
+    /// dongle, this is the value held in the settings cache. It does not reflect
+    /// changes made outside of this object, for example by another program or
+    /// if the dongle has been reset.
+    pub fn channel(&self) -> Channel {
+        self.channel
+    }
+
+    /// Return the datarate last set.
+    ///
+    /// Like for [Crazyradio::channel()], this is the value held in the settings
+    /// cache and not a value read back from the dongle.
+    pub fn datarate(&self) -> Datarate {
+        self.datarate
+    }
+
+    /// Return the radio address last set.
+    ///
+    /// Like for [Crazyradio::channel()], this is the value held in the settings
+    /// cache and not a value read back from the dongle.
+    pub fn address(&self) -> [u8; 5] {
+        self.address
+    }
+
+    /// Return the transmit power last set.
+    ///
+    /// The Crazyradio firmware has no USB request to read the programmed
+    /// power back, the value held by this object is the only record of it.
+    /// It is set to [Power::P0dBm] by [Crazyradio::reset()], so it is
+    /// accurate as long as the dongle has been reset when opened and its power
+    /// has only been changed through this object. After the dongle lost its
+    /// configuration, [Crazyradio::reapply_settings()] programs it again.
+    pub fn power(&self) -> Power {
+        self.power
+    }
+
+    /// Return true if the continuous carrier mode was last enabled.
+    ///
+    /// Like for [Crazyradio::power()], the firmware cannot report the carrier
+    /// state, this is the value last set with [Crazyradio::set_cont_carrier()].
+    /// It is set to false by [Crazyradio::reset()].
+    pub fn cont_carrier(&self) -> bool {
+        self.cont_carrier
+    }
+
+    /// Set the radio channel.
+    pub fn set_channel(&mut self, channel: Channel) -> Result<()> {
+        if !self.cache_settings || self.channel != channel {
+            self.write_control(UsbCommand::SetRadioChannel, channel.0 as u16, &[])?;
+            if self.auto_reset_stats && self.channel != channel {
+                self.reset_link_stats();
+            }
+            self.channel = channel;
+            self.cache_stats.channel_misses += 1;
+        } else {
+            self.cache_stats.channel_hits += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Set the datarate.
+    pub fn set_datarate(&mut self, datarate: Datarate) -> Result<()> {
+        if !self.cache_settings || self.datarate != datarate {
+            self.write_control(UsbCommand::SetDataRate, datarate as u16, &[])?;
+            self.datarate = datarate;
+            self.cache_stats.datarate_misses += 1;
+
+            if self.auto_ard {
+                self.set_ard_time(auto_ard_time(datarate))?;
+            }
+        } else {
+            self.cache_stats.datarate_hits += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Enable or disable setting the ARD automatically when the datarate changes
+    ///
+    /// When enabled, [Crazyradio::set_datarate()] sets the wait time for the
+    /// ack packet to fit the longest ack at the new datarate: the airtime of
+    /// an ack of [Crazyradio::MAX_ACK_LEN] bytes, see [Datarate::airtime()],
+    /// plus the 130us nRF24 settling time, rounded up to the next ARD step.
+    /// The ARD is set for the current datarate when enabling it. Disabled by
+    /// default.
+    ///
+    /// This is only needed after setting the ARD as a time: an ARD set with
+    /// [Crazyradio::set_ard_bytes()], as done by [Crazyradio::reset()], is
+    /// already computed by the dongle for the current datarate.
+    pub fn set_auto_ard(&mut self, enable: bool) -> Result<()> {
+        self.auto_ard = enable;
+        if enable {
+            self.set_ard_time(auto_ard_time(self.datarate))?;
+        }
+        Ok(())
+    }
+
+    /// Set the radio address.
+    ///
+    /// The address bytes are in the same order as written in a radio URI,
+    /// see [Address] for the conversion from and to the URI format.
+    pub fn set_address(&mut self, address: impl Into<Address>) -> Result<()> {
+        let address = address.into();
+        if !self.cache_settings || self.address != *address.bytes() {
+            self.write_control(UsbCommand::SetRadioAddress, 0, address.bytes())?;
+            self.address = address.into();
+            self.cache_stats.address_misses += 1;
+        } else {
+            self.cache_stats.address_hits += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Set the radio address from an hexadecimal string.
+    ///
+    /// The address is written as 10 hexadecimal digits, optionally prefixed
+    /// by `0x`, the same way as in the radio URIs (see [RadioUri]): the first
+    /// two digits are the first byte passed to [Crazyradio::set_address()].
+    /// Returns [Error::InvalidArgument] if the string is not a valid address.
+    ///
+    /// Example:
+    /// ```no_run
+    /// # use crazyradio::Crazyradio;
+    /// # #[cfg(feature = "rusb")]
+    /// # fn main() -> Result<(), crazyradio::Error> {
+    /// let mut cr = Crazyradio::open_first()?;
+    /// cr.set_address_hex("0xE7E7E7E706")?;
+    /// assert_eq!(cr.address(), [0xe7, 0xe7, 0xe7, 0xe7, 0x06]);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "rusb"))]
+    /// # fn main() {}
+    /// ```
+    pub fn set_address_hex(&mut self, hex: &str) -> Result<()> {
+        let hex = hex
+            .strip_prefix("0x")
+            .or_else(|| hex.strip_prefix("0X"))
+            .unwrap_or(hex);
+        let address = uri::address_from_hex(hex)?;
+        self.set_address(address)
+    }
+
+    /// Set the channel, datarate and address from a radio URI
+    ///
+    /// Like the individual setters, settings that are already set are not
+    /// sent again if caching is enabled.
+    ///
+    /// Example:
+    /// ```no_run
+    /// # use crazyradio::{Crazyradio, RadioUri};
+    /// # #[cfg(feature = "rusb")]
+    /// # fn main() -> Result<(), crazyradio::Error> {
+    /// let mut cr = Crazyradio::open_first()?;
+    /// let uri: RadioUri = "radio://0/80/2M/E7E7E7E7E7".parse()?;
+    /// cr.configure_from_uri(&uri)?;
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "rusb"))]
+    /// # fn main() {}
+    /// ```
+    pub fn configure_from_uri(&mut self, uri: &RadioUri) -> Result<()> {
+        self.set_datarate(uri.datarate())?;
+        self.set_channel(uri.channel())?;
+        self.set_address(uri.address())
+    }
+
+    /// Return the current radio configuration
+    ///
+    /// Like the getters, this contains the values last set and not values
+    /// read back from the dongle.
+    pub fn config(&self) -> RadioConfig {
+        RadioConfig {
+            channel: self.channel,
+            datarate: self.datarate,
+            address: self.address,
+            power: self.power,
+        }
+    }
+
+    /// Apply a radio configuration
+    ///
+    /// Channel, address and datarate are only sent to the dongle if they
+    /// differ from the current settings and caching is enabled.
+    pub fn apply_config(&mut self, config: &RadioConfig) -> Result<()> {
+        self.set_datarate(config.datarate)?;
+        self.set_channel(config.channel)?;
+        self.set_address(config.address)?;
+        self.set_power(config.power)
+    }
+
+    /// Set the transmit power.
+    pub fn set_power(&mut self, power: Power) -> Result<()> {
+        self.write_control(UsbCommand::SetRadioPower, power as u16, &[])?;
+        self.power = power;
+        Ok(())
+    }
+
+    /// Set the transmit power from its value in dBm.
+    ///
+    /// See [Power::from_dbm()] for the values accepted.
+    pub fn set_power_dbm(&mut self, dbm: i8) -> Result<()> {
+        self.set_power(Power::from_dbm(dbm)?)
+    }
+
+    /// Set the transmit power using the raw value of the USB command.
+    ///
+    /// Intended for custom firmware supporting more power levels than the
+    /// stock firmware. The stock firmware only supports the values 0 to 3,
+    /// which correspond to the [Power] levels, and [Crazyradio::set_power()]
+    /// should be used with it.
+    ///
+    /// The raw power cannot be represented by [Power] so it is not tracked:
+    /// [Crazyradio::power()] and [Crazyradio::state()] keep reporting the
+    /// last power set with [Crazyradio::set_power()], and
+    /// [Crazyradio::reapply_settings()] programs this power again.
+    pub fn set_power_raw(&mut self, value: u16) -> Result<()> {
+        self.write_control(UsbCommand::SetRadioPower, value, &[])
+    }
+
+    /// Set how long the radio waits for the ack packet.
+    ///
+    /// The wait time, the nRF24 auto retransmit delay (ARD), is a single
+    /// setting that can be expressed either as a time or as a maximum ack
+    /// payload length, see [AckTimeout]. The last value set replaces the
+    /// previous one, whichever way it has been expressed.
+    pub fn set_ack_timeout(&mut self, timeout: AckTimeout) -> Result<()> {
+        match timeout {
+            AckTimeout::Time(delay) => self.set_ard_time(delay),
+            AckTimeout::Bytes(nbytes) => self.set_ard_bytes(nbytes),
+        }
+    }
+
+    /// Set time to wait for the ack packet.
+    ///
+    /// The nRF24 auto retransmit delay (ARD) is set in steps of 250us, from
+    /// 250us up to 4000us. `delay` is rounded up to the next step, a delay
+    /// longer than 4000us returns [Error::InvalidArgument].
+    ///
+    /// This sets the same setting as [Crazyradio::set_ard_bytes()],
+    /// [Crazyradio::set_ack_timeout()] should be preferred.
+    pub fn set_ard_time(&mut self, delay: Duration) -> Result<()> {
+        let ard = ard_from_time(delay)?;
+        self.set_ard(ard)
+    }
+
+    /// Set time to wait for the ack packet in microseconds.
+    ///
+    /// `micros` must be one of the nRF24 auto retransmit delay steps: a
+    /// multiple of 250 from 250 to 4000, otherwise [Error::InvalidArgument]
+    /// is returned. Unlike [Crazyradio::set_ard_time()], the value is never
+    /// rounded.
+    pub fn set_ard_us(&mut self, micros: u16) -> Result<()> {
+        if (250..=4000).contains(&micros) && micros % 250 == 0 {
+            self.set_ard(micros / 250 - 1)
+        } else {
+            Err(Error::InvalidArgument)
+        }
+    }
+
+    /// Set time to wait for the ack packet by specifying the max byte-length of the ack payload.
+    ///
+    /// The Crazyradio always uses the nRF24 dynamic payload length (DPL)
+    /// feature, this cannot be changed. The wait time is computed by the
+    /// dongle to fit an ack of `nbytes` at the current datarate; it does not
+    /// configure a fixed ack payload length.
+    ///
+    /// This sets the same setting as [Crazyradio::set_ard_time()],
+    /// [Crazyradio::set_ack_timeout()] should be preferred.
+    pub fn set_ard_bytes(&mut self, nbytes: u8) -> Result<()> {
+        if nbytes as usize <= MAX_ACK_LEN {
+            self.set_ard(0x80 | nbytes as u16)
+        } else {
+            Err(Error::InvalidArgument)
+        }
+    }
+
+    // Set the ARD using the raw value of the USB command
+    fn set_ard(&mut self, ard: u16) -> Result<()> {
+        self.write_control(UsbCommand::SetRadioArd, ard, &[])?;
+        self.ard = ard;
+        Ok(())
+    }
+
+    // Send a vendor control command to the dongle
+    fn write_control(&mut self, command: UsbCommand, value: u16, data: &[u8]) -> Result<()> {
+        self.transport
+            .control_out(command as u8, value, 0, data, self.control_timeout)
+            .map_err(Into::into)?;
+        Ok(())
+    }
+
+    /// Set the number of time the radio will retry to send the packet if an ack packet is not received in time.
+    ///
+    /// `arc` goes from 0 to 15. With 0 the packet is sent once without any
+    /// retry, see [Crazyradio::disable_retransmission()].
+    pub fn set_arc(&mut self, arc: usize) -> Result<()> {
+        if arc <= 15 {
+            self.write_control(UsbCommand::SetRadioArc, arc as u16, &[])?;
+            self.arc = arc;
+            Ok(())
+        } else {
+            Err(Error::InvalidArgument)
+        }
+    }
+
+    /// Disable the automatic retransmission of packets not acked.
+    ///
+    /// Each packet is sent once, whether an ack is received can be checked
+    /// in the returned [Ack]. This is the same as `set_arc(0)` and is intended
+    /// for applications handling the retransmission themselves, for example
+    /// to always send the latest real-time setpoint instead of retrying an
+    /// outdated one.
+    pub fn disable_retransmission(&mut self) -> Result<()> {
+        self.set_arc(0)
+    }
+
+    /// Set both the number of retries and the time to wait for the ack packet.
+    ///
+    /// See [Crazyradio::set_arc()] and [Crazyradio::set_ard_time()]. Both
+    /// values are validated before anything is sent to the dongle, an
+    /// [Error::InvalidArgument] is returned if `arc` is greater than 15,
+    /// if `ard` is longer than 4000us or if `ard` is shorter than 500us while
+    /// the datarate is 250Kbps, which is the minimum supported by the nRF24
+    /// at this datarate.
+    pub fn set_retransmit(&mut self, arc: usize, ard: Duration) -> Result<()> {
+        let ard_value = ard_from_time(ard)?;
+
+        if arc > 15 || (self.datarate == Datarate::Dr250K && ard < Duration::from_micros(500)) {
+            return Err(Error::InvalidArgument);
+        }
+
+        self.set_arc(arc)?;
+        self.set_ard(ard_value)
+    }
+
+    /// Set if the radio waits for an ack packet.
     ///
     /// Should be disabled when sending broadcast packets.
     pub fn set_ack_enable(&mut self, ack_enable: bool) -> Result<()> {
-        self.device_handle.write_control(
-            0x40,
-            UsbCommand::AckEnable as u8,
-            ack_enable as u16,
-            0,
-            &[],
-            Duration::from_secs(1),
-        )?;
+        self.write_control(UsbCommand::AckEnable, ack_enable as u16, &[])?;
+        self.ack_enable = ack_enable;
         Ok(())
     }
 
-    /// Sends a packet to a range of channel and returns a list of channel that acked
-    ///
-    /// Used to activally scann for receives on channels. This function sends
-    pub fn scan_channels(
-        &mut self,
-        start: Channel,
-        stop: Channel,
-        packet: &[u8],
-    ) -> Result<Vec<Channel>> {
+    /// Sends a packet to a range of channel and returns a list of channel that acked
+    ///
+    /// Used to activally scann for receives on channels. This function sends
+    pub fn scan_channels(
+        &mut self,
+        start: Channel,
+        stop: Channel,
+        packet: &[u8],
+    ) -> Result<Vec<Channel>> {
+        let mut result: Vec<Channel> = vec![];
+        self.scan_channels_each(start, stop, packet, |channel| result.push(channel))?;
+        Ok(result)
+    }
+
+    /// Sends a packet to all the channels and returns a list of channel that acked
+    ///
+    /// The channels are scanned in the order of [Channel::scan_order()], the
+    /// channels found are returned in the same order. Same as
+    /// [Crazyradio::scan_channels()] otherwise.
+    pub fn scan_channels_ordered(&mut self, packet: &[u8]) -> Result<Vec<Channel>> {
+        let mut ack_data = [0u8; MAX_ACK_LEN];
+        let mut result = vec![];
+        for channel in Channel::scan_order() {
+            self.set_channel(channel)?;
+            if self.send_packet(packet, &mut ack_data)?.received {
+                result.push(channel);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Sends a packet to a [ChannelRange] and returns a list of channel that acked
+    ///
+    /// Same as [Crazyradio::scan_channels()].
+    pub fn scan_range(&mut self, range: ChannelRange, packet: &[u8]) -> Result<Vec<Channel>> {
+        self.scan_channels(range.start, range.stop, packet)
+    }
+
+    /// Sends a packet to a [ChannelRange] using `address` and returns a list of channel that acked
+    ///
+    /// The address is set for the scan and the previous address is set back
+    /// afterward, even if the scan failed. Same as [Crazyradio::scan_range()]
+    /// otherwise.
+    ///
+    /// The Crazyradio firmware only supports 5 bytes addresses, devices using
+    /// another address width cannot be scanned.
+    pub fn scan_channels_with_address(
+        &mut self,
+        range: ChannelRange,
+        address: impl Into<Address>,
+        packet: &[u8],
+    ) -> Result<Vec<Channel>> {
+        let previous = self.address();
+        self.set_address(address)?;
+
+        let result = self.scan_range(range, packet);
+
+        self.set_address(previous)?;
+        result
+    }
+
+    /// Sends a packet to a [ChannelRange] and iterates over the channels that acked
+    ///
+    /// The scan is lazy: each call to `next()` scans the following channels
+    /// until one acks or the end of the range is reached. This allows to stop
+    /// the scan early, for example after the first channel found:
+    /// ```no_run
+    /// # use crazyradio::{Crazyradio, Channel, ChannelRange};
+    /// # #[cfg(feature = "rusb")]
+    /// # fn main() -> Result<(), crazyradio::Error> {
+    /// let mut cr = Crazyradio::open_first()?;
+    /// let range = ChannelRange::new(Channel::from_number(0)?, Channel::from_number(125)?)?;
+    /// let first = cr.scan_channels_iter(range, &[0xff]).next().transpose()?;
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "rusb"))]
+    /// # fn main() {}
+    /// ```
+    ///
+    /// USB errors are returned by the iterator, the scan then continues with
+    /// the next channel.
+    pub fn scan_channels_iter<'a>(
+        &'a mut self,
+        range: ChannelRange,
+        packet: &'a [u8],
+    ) -> impl Iterator<Item = Result<Channel>> + 'a {
+        let mut ack_data = [0u8; MAX_ACK_LEN];
+        range.iter().filter_map(move |channel| {
+            let ack = self
+                .set_channel(channel)
+                .and_then(|_| self.send_packet(packet, &mut ack_data));
+            match ack {
+                Ok(ack) if ack.received => Some(Ok(channel)),
+                Ok(_) => None,
+                Err(e) => Some(Err(e)),
+            }
+        })
+    }
+
+    /// Scan a [ChannelRange] repeatedly until a channel acks or `timeout` elapses
+    ///
+    /// The range is scanned in order, again and again, until `probe` is
+    /// acked. Intended to connect to a device that is still booting when the
+    /// scan starts. The radio is left on the channel found. Returns `None`
+    /// if no channel acked within `timeout`, at least one channel is always
+    /// scanned.
+    pub fn scan_until(
+        &mut self,
+        range: ChannelRange,
+        probe: &[u8],
+        timeout: Duration,
+    ) -> Result<Option<Channel>> {
+        let start = Instant::now();
+        let mut ack_data = [0u8; MAX_ACK_LEN];
+
+        loop {
+            for channel in range.iter() {
+                self.set_channel(channel)?;
+                if self.send_packet(probe, &mut ack_data)?.received {
+                    return Ok(Some(channel));
+                }
+                if start.elapsed() >= timeout {
+                    return Ok(None);
+                }
+            }
+        }
+    }
+
+    /// Sends a packet to a range of channel and calls `f` for each channel that acked
+    ///
+    /// Works like [Crazyradio::scan_channels()] but reports the channels as
+    /// soon as they are found instead of returning them all at the end of the
+    /// scan. This allows to display the scan result incrementally.
+    pub fn scan_channels_each(
+        &mut self,
+        start: Channel,
+        stop: Channel,
+        packet: &[u8],
+        mut f: impl FnMut(Channel),
+    ) -> Result<()> {
+        let mut ack_data = [0u8; MAX_ACK_LEN];
+        for ch in start.0..stop.0 + 1 {
+            let channel = Channel::from_number(ch).unwrap();
+            self.set_channel(channel)?;
+            let ack = self.send_packet(packet, &mut ack_data)?;
+            if ack.received {
+                f(channel);
+            }
+        }
+        Ok(())
+    }
+
+    /// Find the channel on which a device with a known address and datarate now answers
+    ///
+    /// Sets the address and datarate and sends `probe` on each channel of
+    /// `range`, in order, until the device acks. Intended to find a device
+    /// that moved to another channel, for example after a reboot.
+    ///
+    /// Returns the first channel where the packet is acked, the radio is then
+    /// left on this channel. If the device is not found, `None` is returned
+    /// and the radio channel is set back to its previous value. The address
+    /// and datarate are not restored.
+    pub fn reacquire(
+        &mut self,
+        address: &[u8; 5],
+        datarate: Datarate,
+        range: ChannelRange,
+        probe: &[u8],
+    ) -> Result<Option<Channel>> {
+        let prev_channel = self.channel;
+        let mut ack_data = [0u8; MAX_ACK_LEN];
+
+        self.set_address(address)?;
+        self.set_datarate(datarate)?;
+
+        for channel in range.iter() {
+            self.set_channel(channel)?;
+            if self.send_packet(probe, &mut ack_data)?.received {
+                return Ok(Some(channel));
+            }
+        }
+
+        self.set_channel(prev_channel)?;
+
+        Ok(None)
+    }
+
+    /// Find the candidate channel where a device acks best
+    ///
+    /// Sets the address and datarate and sends `probe` once on each channel
+    /// of `candidates`. The best channel is the one with the fewest retries,
+    /// ties are broken in favor of the power detector being set, then of the
+    /// first candidate. Intended to pick one channel after a scan found a
+    /// device on several adjacent channels.
+    ///
+    /// The radio is left on the best channel. If the device does not ack on
+    /// any candidate, `None` is returned and the radio channel is set back to
+    /// its previous value. The address and datarate are not restored.
+    pub fn best_channel(
+        &mut self,
+        candidates: &[Channel],
+        address: &[u8; 5],
+        datarate: Datarate,
+        probe: &[u8],
+    ) -> Result<Option<Channel>> {
+        let prev_channel = self.channel;
+        let mut ack_data = [0u8; MAX_ACK_LEN];
+        let mut best: Option<(Channel, (usize, bool))> = None;
+
+        self.set_address(address)?;
+        self.set_datarate(datarate)?;
+
+        for &channel in candidates {
+            self.set_channel(channel)?;
+            let ack = self.send_packet(probe, &mut ack_data)?;
+            if !ack.received {
+                continue;
+            }
+
+            let rank = ack_rank(&ack);
+            match best {
+                Some((_, best_rank)) if best_rank <= rank => (),
+                _ => best = Some((channel, rank)),
+            }
+        }
+
+        match best {
+            Some((channel, _)) => {
+                self.set_channel(channel)?;
+                Ok(Some(channel))
+            }
+            None => {
+                self.set_channel(prev_channel)?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Scan a [ChannelRange] at all the datarates and return the best datarate of each channel that acked
+    ///
+    /// The packet is sent on each channel at 2M, 1M and 250K. For each
+    /// channel where at least one datarate got an ack, the best datarate is
+    /// reported, ranked like in [Crazyradio::best_channel()]: the ack with
+    /// the fewest retries, then the one with the power detector set. On
+    /// equality the fastest datarate is preferred. The channels found are
+    /// returned in increasing order. The radio channel and datarate are set
+    /// back to their previous values at the end of the scan.
+    pub fn scan_all(&mut self, range: ChannelRange, packet: &[u8]) -> Result<Vec<ScanFound>> {
+        let prev_channel = self.channel;
+        let prev_datarate = self.datarate;
+        let mut ack_data = [0u8; MAX_ACK_LEN];
+        let mut found: Vec<(ScanFound, (usize, bool))> = vec![];
+
+        for datarate in [Datarate::Dr2M, Datarate::Dr1M, Datarate::Dr250K] {
+            self.set_datarate(datarate)?;
+
+            for channel in range.iter() {
+                self.set_channel(channel)?;
+                let ack = self.send_packet(packet, &mut ack_data)?;
+                if !ack.received {
+                    continue;
+                }
+
+                let candidate = (
+                    ScanFound {
+                        channel,
+                        datarate,
+                        retry: ack.retry,
+                    },
+                    ack_rank(&ack),
+                );
+                match found.iter_mut().find(|(f, _)| f.channel == channel) {
+                    Some(best) if candidate.1 < best.1 => *best = candidate,
+                    Some(_) => (),
+                    None => found.push(candidate),
+                }
+            }
+        }
+
+        self.set_datarate(prev_datarate)?;
+        self.set_channel(prev_channel)?;
+
+        let mut found: Vec<ScanFound> = found.into_iter().map(|(f, _)| f).collect();
+        found.sort_by_key(|f| f.channel.0);
+        Ok(found)
+    }
+
+    /// Find the channel with the least activity in a range
+    ///
+    /// For each channel of the range, `samples` null packets (`[0xff]`) are
+    /// sent and the channel where the least activity has been detected is
+    /// returned. Activity is detected when the nRF24 power detector reports
+    /// energy on the channel or when a device acks the packet. If multiple
+    /// channels are equally quiet, the lowest one is returned.
+    ///
+    /// The power detector is only sampled while the radio waits for an ack,
+    /// so this function should be used with ack enabled. The radio channel is
+    /// set back to its previous value at the end of the measurement.
+    ///
+    /// Returns [Error::InvalidArgument] if `samples` is 0.
+    pub fn find_quiet_channel(&mut self, range: ChannelRange, samples: usize) -> Result<Channel> {
+        if samples == 0 {
+            return Err(Error::InvalidArgument);
+        }
+
+        let prev_channel = self.channel;
+        let mut ack_data = [0u8; MAX_ACK_LEN];
+        let mut quietest = (range.start(), usize::MAX);
+
+        for channel in range.iter() {
+            self.set_channel(channel)?;
+
+            let mut detections = 0;
+            for _ in 0..samples {
+                let ack = self.send_packet(&[0xff], &mut ack_data)?;
+                if ack.received || ack.power_detector {
+                    detections += 1;
+                }
+            }
+
+            if detections < quietest.1 {
+                quietest = (channel, detections);
+            }
+        }
+
+        self.set_channel(prev_channel)?;
+
+        Ok(quietest.0)
+    }
+
+    /// Launch the bootloader.
+    ///
+    /// Consumes the Crazyradio since it is not usable after that (it is in bootlaoder mode ...).
+    /// The dongle in bootloader mode can be found with [Crazyradio::list_bootloaders()].
+    ///
+    /// This crate does not implement the bootloader protocol, the firmware
+    /// has to be flashed with the tools of the
+    /// [crazyradio-firmware](https://github.com/bitcraze/crazyradio-firmware)
+    /// repository.
+    pub fn launch_bootloader(mut self) -> Result<()> {
+        self.write_control(UsbCommand::LaunchBootloader, 0, &[])?;
+        Ok(())
+    }
+
+    /// Set the radio in continious carrier mode.
+    ///
+    /// In continious carrier mode, the radio will transmit a continious sine
+    /// wave at the setup channel frequency using the setup transmit power.
+    pub fn set_cont_carrier(&mut self, enable: bool) -> Result<()> {
+        self.write_control(UsbCommand::SetContCarrier, enable as u16, &[])?;
+        self.cont_carrier = enable;
+        Ok(())
+    }
+
+    /// Start emitting a continuous carrier on `channel` at `power`.
+    ///
+    /// The channel and power are set before enabling the continuous carrier
+    /// so that the radio does not emit, even briefly, with the previous
+    /// settings. The carrier is stopped with [Crazyradio::stop_carrier()].
+    pub fn start_carrier(&mut self, channel: Channel, power: Power) -> Result<()> {
+        self.set_channel(channel)?;
+        self.set_power(power)?;
+        self.set_cont_carrier(true)
+    }
+
+    /// Stop the continuous carrier started with [Crazyradio::start_carrier()].
+    pub fn stop_carrier(&mut self) -> Result<()> {
+        self.set_cont_carrier(false)
+    }
+
+    /// Send a data packet and receive an ack packet.
+    ///
+    /// # Arguments
+    ///
+    ///  * `data`: Up to [Crazyradio::MAX_PACKET_LEN] bytes of data to be send.
+    ///  * `ack_data`: Buffer to hold the data received from the ack packet
+    ///    payload. The ack payload can be up to [Crazyradio::MAX_ACK_LEN]
+    ///    bytes, if this buffer is shorter the ack data might
+    ///    be truncated. The length of the ack payload is returned
+    ///    in Ack::length.
+    ///
+    /// The Crazyradio always uses dynamic payload length, the ack length is
+    /// the one received over the air. Devices configured with fixed-length
+    /// payloads are not supported. If an expected length has been set with
+    /// [Crazyradio::set_expected_ack_length()], [Error::AckLengthMismatch] is
+    /// returned when an ack of another length is received.
+    ///
+    /// Returns [Error::InvalidArgument] if `data` is longer than
+    /// [Crazyradio::MAX_PACKET_LEN], nothing is sent then.
+    pub fn send_packet(&mut self, data: &[u8], ack_data: &mut [u8]) -> Result<Ack> {
+        self.send_packet_until(data, ack_data, None)
+    }
+
+    /// Send a data packet to a channel, address and datarate, then restore the previous ones.
+    ///
+    /// Intended to send a single packet to another device without changing
+    /// the link configured in the radio. The channel, address and datarate
+    /// are set back after the packet has been sent, even if sending failed.
+    /// With the settings cache enabled, the settings that are already the
+    /// ones requested are neither set nor restored.
+    ///
+    /// Same as [Crazyradio::send_packet()] otherwise.
+    pub fn send_packet_oneshot(
+        &mut self,
+        channel: Channel,
+        address: impl Into<Address>,
+        datarate: Datarate,
+        data: &[u8],
+        ack_data: &mut [u8],
+    ) -> Result<Ack> {
+        let (prev_channel, prev_address, prev_datarate) =
+            (self.channel, self.address, self.datarate);
+
+        self.set_channel(channel)?;
+        self.set_address(address)?;
+        self.set_datarate(datarate)?;
+
+        let result = self.send_packet(data, ack_data);
+
+        self.set_channel(prev_channel)?;
+        self.set_address(prev_address)?;
+        self.set_datarate(prev_datarate)?;
+
+        result
+    }
+
+    /// Send a broadcast packet on a channel to [Crazyradio::BROADCAST_ADDRESS]
+    ///
+    /// Sets the channel and the broadcast address and sends the packet with
+    /// [Crazyradio::send_broadcast()]. The channel and address are left set to
+    /// the broadcast ones.
+    pub fn broadcast_to_default(&mut self, channel: Channel, data: &[u8]) -> Result<()> {
+        self.set_channel(channel)?;
+        self.set_address(BROADCAST_ADDRESS)?;
+        self.send_broadcast(data)
+    }
+
+    /// Send a data packet and receive an ack packet before a deadline.
+    ///
+    /// Works like [Crazyradio::send_packet()] but the USB transfers timeout
+    /// is the time remaining until `deadline` instead of the transfer timeout
+    /// set with [Crazyradio::set_transfer_timeout()]. Returns [Error::Timeout]
+    /// if the deadline has passed before the packet is sent or before the
+    /// ack is received. Intended for real-time loops where `deadline` is
+    /// computed from the loop period.
+    ///
+    /// If the packet has been sent but its ack has not been received before
+    /// the deadline, the ack is read and discarded before sending the next
+    /// packet so that each packet is matched with its own ack.
+    pub fn send_packet_deadline(
+        &mut self,
+        data: &[u8],
+        ack_data: &mut [u8],
+        deadline: Instant,
+    ) -> Result<Ack> {
+        self.send_packet_until(data, ack_data, Some(deadline))
+            .map_err(|e| match e {
+                #[cfg(feature = "rusb")]
+                Error::UsbError(rusb::Error::Timeout) => Error::Timeout,
+                e => e,
+            })
+    }
+
+    // Timeout of the next USB transfer, the remaining time if there is a deadline
+    fn timeout_until(&self, deadline: Option<Instant>) -> Result<Duration> {
+        match deadline {
+            None => Ok(self.transfer_timeout),
+            // A zero timeout means no timeout for rusb, it has to be caught here
+            Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                Some(remaining) if !remaining.is_zero() => Ok(remaining),
+                _ => Err(Error::Timeout),
+            },
+        }
+    }
+
+    // Send a packet, the USB transfers timeout being computed from `deadline` if set
+    fn send_packet_until(
+        &mut self,
+        data: &[u8],
+        ack_data: &mut [u8],
+        deadline: Option<Instant>,
+    ) -> Result<Ack> {
+        if data.len() > MAX_PACKET_LEN {
+            return Err(Error::InvalidArgument);
+        }
+
+        // The dongle queues the status of each packet, the one of a timed out transfer comes first
+        if self.status_pending {
+            let mut discarded = [0u8; MAX_ACK_LEN + 1];
+            let timeout = self.timeout_until(deadline)?;
+            self.transport
+                .bulk_in(0x81, &mut discarded, timeout)
+                .map_err(Into::into)?;
+            self.status_pending = false;
+        }
+
+        let timeout = self.timeout_until(deadline)?;
+        let written = self
+            .transport
+            .bulk_out(0x01, data, timeout)
+            .map_err(Into::into)?;
+        // The packet is sent as one USB transfer, a partial write would send a truncated packet
+        if written != data.len() {
+            return Err(Error::PartialWrite);
+        }
+        self.status_pending = true;
+
+        // Status byte followed by the ack payload
+        let mut received_data = [0u8; MAX_ACK_LEN + 1];
+        let timeout = self.timeout_until(deadline)?;
+        let received = self
+            .transport
+            .bulk_in(0x81, &mut received_data, timeout)
+            .map_err(Into::into)?;
+        self.status_pending = false;
+
+        let copied = ack_data.len().min(MAX_ACK_LEN);
+        ack_data[..copied].copy_from_slice(&received_data[1..copied + 1]);
+
+        let ack = Ack::from_status_byte(received_data[0], received.saturating_sub(1));
+
+        // Packets sent without ack do not tell anything about the link
+        if self.ack_enable {
+            self.link_stats.sent += 1;
+            self.link_stats.retries += ack.retry as u64;
+
+            if ack.received {
+                self.link_stats.acked += 1;
+                self.link_stats.max_ack_length = self.link_stats.max_ack_length.max(ack.length);
+
+                self.consecutive_lost = 0;
+            } else {
+                self.consecutive_lost = self.consecutive_lost.saturating_add(1);
+            }
+        }
+
+        match self.expected_ack_length {
+            Some(length) if ack.received && ack.length != length => Err(Error::AckLengthMismatch),
+            _ => Ok(ack),
+        }
+    }
+
+    /// Set the length the ack payloads are expected to have
+    ///
+    /// When set, [Crazyradio::send_packet()] returns
+    /// [Error::AckLengthMismatch] if an ack is received with a payload of
+    /// another length. For devices always answering with the same ack length,
+    /// this allows to detect corrupted acks. Disabled by default (`None`).
+    pub fn set_expected_ack_length(&mut self, length: Option<usize>) {
+        self.expected_ack_length = length;
+    }
+
+    /// Send a data packet and fail with [Error::LinkDown] if the link is considered down.
+    ///
+    /// Works like [Crazyradio::send_packet()] but returns [Error::LinkDown]
+    /// if the packet is not acked and the number of consecutive packets
+    /// not acked has reached the threshold set with
+    /// [Crazyradio::set_link_down_threshold()]. This allows to detect that
+    /// the device is gone without having to count the lost packets.
+    ///
+    /// Once the link is down, [Error::LinkDown] is returned immediately
+    /// without sending the packet, so that a loop does not wait for the USB
+    /// transfers of a device that is gone. The link is detected as up again
+    /// when a packet sent with [Crazyradio::send_packet()] is acked.
+    pub fn send_packet_checked(&mut self, data: &[u8], ack_data: &mut [u8]) -> Result<Ack> {
+        if self.is_link_down() {
+            return Err(Error::LinkDown);
+        }
+
+        let ack = self.send_packet(data, ack_data)?;
+
+        if self.is_link_down() {
+            Err(Error::LinkDown)
+        } else {
+            Ok(ack)
+        }
+    }
+
+    /// Send `pattern` and check that it is echoed back in the ack payload.
+    ///
+    /// Returns true if an ack is received with a non-empty payload equal to
+    /// the first bytes of `pattern`. An empty payload or a payload longer
+    /// than `pattern` is not an echo. This distinguishes a healthy link from an ack received with
+    /// an unexpected payload, for example because of interference or because
+    /// another device answered. It requires the receiving device to echo the
+    /// packets it receives.
+    ///
+    /// Returns [Error::InvalidArgument] if `pattern` is longer than
+    /// [Crazyradio::MAX_PACKET_LEN].
+    pub fn verify_echo(&mut self, pattern: &[u8]) -> Result<bool> {
+        if pattern.len() > MAX_PACKET_LEN {
+            return Err(Error::InvalidArgument);
+        }
+
+        let mut ack_data = [0u8; MAX_ACK_LEN];
+        let ack = self.send_packet(pattern, &mut ack_data)?;
+
+        Ok(ack.received
+            && ack.length > 0
+            && ack.length <= pattern.len()
+            && ack_data[..ack.length] == pattern[..ack.length])
+    }
+
+    /// Set the number of consecutive packets not acked after which the link is considered down.
+    ///
+    /// Defaults to 100.
+    pub fn set_link_down_threshold(&mut self, threshold: usize) {
+        self.link_down_threshold = threshold;
+    }
+
+    /// Return true if the last packets sent have not been acked.
+    ///
+    /// The link is considered down when the number of consecutive packets not
+    /// acked has reached the threshold set with
+    /// [Crazyradio::set_link_down_threshold()].
+    pub fn is_link_down(&self) -> bool {
+        self.consecutive_lost >= self.link_down_threshold
+    }
+
+    /// Return the length of the longest ack payload received since the last reset of the link statistics
+    ///
+    /// Can be used to size the ack buffers or to tune the ack wait time with
+    /// [Crazyradio::set_ard_bytes()].
+    pub fn max_ack_length(&self) -> usize {
+        self.link_stats.max_ack_length
+    }
+
+    /// Return the statistics accumulated since the last reset of the link statistics
+    ///
+    /// Only the packets sent with ack enabled are accounted for.
+    ///
+    /// The Crazyradio firmware does not give access to the nRF24 OBSERVE_TX
+    /// register, so the hardware lost packet counter (PLOS_CNT) cannot be
+    /// read. The statistics are computed from the ack status returned for each
+    /// packet: the retry count is the nRF24 ARC_CNT of the packet, and a
+    /// packet not acked after all the retries is counted as lost.
+    pub fn link_stats(&self) -> LinkStats {
+        self.link_stats
+    }
+
+    /// Reset the link statistics
+    pub fn reset_link_stats(&mut self) {
+        self.link_stats = LinkStats::default();
+    }
+
+    /// Enable or disable resetting the link statistics when the channel changes
+    ///
+    /// When enabled, [Crazyradio::set_channel()] resets the link statistics
+    /// when it sets a channel different from the current one, so that
+    /// [Crazyradio::link_stats()] only covers the current channel. Disabled
+    /// by default.
+    pub fn set_auto_reset_stats(&mut self, enable: bool) {
+        self.auto_reset_stats = enable;
+    }
+
+    /// Send a broadcast packet.
+    ///
+    /// Broadcast packets are sent without waiting for an ack. If ack is
+    /// enabled, it is disabled for the time of the transmission and enabled
+    /// back afterward. If ack is already disabled, for example because the
+    /// radio is only used to send broadcasts, no extra USB command is sent.
+    pub fn send_broadcast(&mut self, data: &[u8]) -> Result<()> {
+        let ack_enable = self.ack_enable;
+
+        if ack_enable {
+            self.set_ack_enable(false)?;
+        }
+
+        let mut ack_data = [0u8; MAX_ACK_LEN];
+        let result = self.send_packet(data, &mut ack_data);
+
+        if ack_enable {
+            self.set_ack_enable(true)?;
+        }
+
+        result.map(|_| ())
+    }
+
+    /// Send a data packet, receive an ack packet and measure the round-trip time.
+    ///
+    /// Works like [Crazyradio::send_packet()] and additionally returns the
+    /// time elapsed between the start of the USB write and the end of the
+    /// USB read of the ack. This includes the radio transmission and all the
+    /// hardware retries.
+    pub fn send_packet_timed(
+        &mut self,
+        data: &[u8],
+        ack_data: &mut [u8],
+    ) -> Result<(Ack, Duration)> {
+        let start = Instant::now();
+        let ack = self.send_packet(data, ack_data)?;
+        Ok((ack, start.elapsed()))
+    }
+
+    /// Send a data packet, receive an ack packet and return statistics about the exchange.
+    ///
+    /// Works like [Crazyradio::send_packet()] but returns a [PacketStats]
+    /// that gathers the ack status and the measured round-trip time. Intended
+    /// to be used for per-packet link telemetry.
+    pub fn send_packet_stats(&mut self, data: &[u8], ack_data: &mut [u8]) -> Result<PacketStats> {
+        let (ack, round_trip) = self.send_packet_timed(data, ack_data)?;
+
+        Ok(PacketStats {
+            ack,
+            retry: ack.retry,
+            power_detector: ack.power_detector,
+            round_trip,
+        })
+    }
+
+    /// Measure the packet rate achieved by sending `packet` repeatedly during `duration`.
+    ///
+    /// Packets are sent back to back with [Crazyradio::send_packet()] and the
+    /// result accounts for the whole exchange: USB transfers, radio
+    /// transmission, retries and ack. The bytes counted are the bytes sent
+    /// plus the ack payload bytes received. The measurement runs for at
+    /// least `duration`, the time to send the last packet is included.
+    pub fn measure_throughput(&mut self, packet: &[u8], duration: Duration) -> Result<Throughput> {
+        let mut ack_data = [0u8; MAX_ACK_LEN];
+        let mut throughput = Throughput::default();
+
+        let start = Instant::now();
+        while start.elapsed() < duration {
+            let ack = self.send_packet(packet, &mut ack_data)?;
+
+            throughput.packets += 1;
+            throughput.bytes += packet.len() as u64;
+            if ack.received {
+                throughput.acked += 1;
+                throughput.bytes += ack.length as u64;
+            }
+        }
+        throughput.elapsed = start.elapsed();
+
+        Ok(throughput)
+    }
+
+    /// Send data longer than a radio packet by splitting it in multiple packets.
+    ///
+    /// `data` is split in chunks of up to [Crazyradio::MAX_PACKET_LEN] bytes
+    /// that are sent one after the other. No header is added to the packets,
+    /// any framing required to reassemble the data on the receiving side has
+    /// to be part of `data`.
+    ///
+    /// Returns the acks of the packets sent. The transmission stops at the
+    /// first packet that is not acked, its ack is the last one of the list.
+    /// The content of the ack payloads is discarded.
+    pub fn send_fragmented(&mut self, data: &[u8]) -> Result<Vec<Ack>> {
+        let mut ack_data = [0u8; MAX_ACK_LEN];
+        let mut acks = vec![];
+
+        for chunk in data.chunks(MAX_PACKET_LEN) {
+            let ack = self.send_packet(chunk, &mut ack_data)?;
+            acks.push(ack);
+            if !ack.received {
+                break;
+            }
+        }
+
+        Ok(acks)
+    }
+
+    /// Send packets in order until one is not acked.
+    ///
+    /// Returns the number of packets acked before the first packet that was
+    /// not acked, or the number of packets if all of them were acked. This is
+    /// the index of the packet to resend to resume the transmission. The
+    /// content of the ack payloads is discarded.
+    pub fn send_sequence(&mut self, packets: &[&[u8]]) -> Result<usize> {
+        let mut ack_data = [0u8; MAX_ACK_LEN];
+
+        for (i, packet) in packets.iter().enumerate() {
+            if !self.send_packet(packet, &mut ack_data)?.received {
+                return Ok(i);
+            }
+        }
+
+        Ok(packets.len())
+    }
+
+    /// Send the same packet to multiple devices, each with its own channel and address.
+    ///
+    /// The datarate is set once and, for each target, the channel and address
+    /// are set and `data` is sent. The targets are sent to in order of
+    /// channel so that, with caching enabled, the channel is only changed
+    /// once per different channel. The channel and address are left to the
+    /// ones of the last target sent to.
+    ///
+    /// Returns the acks in the same order as `targets`.
+    pub fn multicast(
+        &mut self,
+        targets: &[(Channel, [u8; 5])],
+        datarate: Datarate,
+        data: &[u8],
+    ) -> Result<Vec<Ack>> {
+        let mut ack_data = [0u8; MAX_ACK_LEN];
+        let mut acks = vec![None; targets.len()];
+
+        let mut order: Vec<usize> = (0..targets.len()).collect();
+        order.sort_by_key(|&i| targets[i].0 .0);
+
+        self.set_datarate(datarate)?;
+
+        for i in order {
+            let (channel, address) = targets[i];
+            self.set_channel(channel)?;
+            self.set_address(address)?;
+            acks[i] = Some(self.send_packet(data, &mut ack_data)?);
+        }
+
+        Ok(acks.into_iter().flatten().collect())
+    }
+}
+
+/// # Experimental functions
+///
+/// Raw access to the dongle vendor control requests.
+///
+/// These functions are intended to prototype new firmware features before
+/// they are supported by this crate. **Warning:** the requests are sent
+/// as-is. Sending the wrong request can leave the dongle in an unexpected
+/// state, or launch the bootloader, and the settings cache of this object is
+/// not updated.
+#[cfg(feature = "experimental")]
+#[cfg_attr(docsrs, doc(cfg(feature = "experimental")))]
+impl<T: UsbTransport> Crazyradio<T> {
+    /// Send a raw vendor OUT control request to the dongle
+    pub fn raw_control(&mut self, request: u8, value: u16, index: u16, data: &[u8]) -> Result<()> {
+        self.transport
+            .control_out(request, value, index, data, self.control_timeout)
+            .map_err(Into::into)?;
+        Ok(())
+    }
+}
+
+#[cfg(all(feature = "experimental", feature = "rusb"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "experimental", feature = "rusb"))))]
+impl Crazyradio {
+    /// Send a raw vendor IN control request to the dongle
+    ///
+    /// The data received is written in `data`, its length is returned.
+    pub fn raw_control_read(
+        &mut self,
+        request: u8,
+        value: u16,
+        index: u16,
+        data: &mut [u8],
+    ) -> Result<usize> {
+        Ok(self.transport.device_handle.read_control(
+            0xc0,
+            request,
+            value,
+            index,
+            data,
+            self.control_timeout,
+        )?)
+    }
+}
+
+/// Options to select and open a Crazyradio
+///
+/// Used with [Crazyradio::open()]. By default the first Crazyradio detected
+/// is opened and reset to boot values.
+#[derive(Debug, Clone)]
+#[cfg(feature = "rusb")]
+pub struct OpenOptions {
+    nth: Option<usize>,
+    serial: Option<String>,
+    reset: bool,
+    detach_kernel_driver: bool,
+    config: Option<RadioConfig>,
+}
+
+#[cfg(feature = "rusb")]
+impl OpenOptions {
+    /// Create the default options: first radio detected, reset enabled
+    pub fn new() -> Self {
+        OpenOptions {
+            nth: None,
+            serial: None,
+            reset: true,
+            detach_kernel_driver: false,
+            config: None,
+        }
+    }
+
+    /// Select the nth Crazyradio detected, see [Crazyradio::open_nth()]
+    pub fn nth(mut self, nth: usize) -> Self {
+        self.nth = Some(nth);
+        self
+    }
+
+    /// Select the Crazyradio by serial number, see [Crazyradio::open_by_serial()]
+    pub fn serial(mut self, serial: &str) -> Self {
+        self.serial = Some(serial.to_owned());
+        self
+    }
+
+    /// Reset the dongle parameters to boot values when opening it
+    ///
+    /// If the reset is disabled, the dongle is left in the state it was when
+    /// opening it. The settings cache still assumes boot values though, so
+    /// caching should be disabled or all the settings should be set before
+    /// relying on it. [OpenOptions::config()] allows to set the cached
+    /// settings when opening the radio.
+    pub fn reset(mut self, reset: bool) -> Self {
+        self.reset = reset;
+        self
+    }
+
+    /// Apply a radio configuration when opening the radio
+    ///
+    /// The configuration is sent to the dongle after the reset, if enabled,
+    /// regardless of the caching configuration. When the reset is disabled
+    /// this puts the channel, datarate and address, which are the cached
+    /// settings, in a known state without resetting the other settings.
+    ///
+    /// Example:
+    /// ```no_run
+    /// use crazyradio::{Crazyradio, OpenOptions, RadioConfig};
+    /// # fn main() -> Result<(), crazyradio::Error> {
+    /// let config = RadioConfig::default();
+    /// let mut cr = Crazyradio::open(OpenOptions::new().reset(false).config(config))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn config(mut self, config: RadioConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Detach the kernel driver bound to the radio, if any, to be able to claim it
+    ///
+    /// The Crazyradio has no kernel driver by default but, on Linux, a
+    /// driver might have been bound to it by another tool. If this option is
+    /// enabled and a kernel driver is active, it is detached when opening the
+    /// radio and reattached when the radio is closed. Disabled by default.
+    ///
+    /// Detaching kernel drivers is only supported on Linux, this option has
+    /// no effect on other platforms.
+    pub fn detach_kernel_driver(mut self, detach: bool) -> Self {
+        self.detach_kernel_driver = detach;
+        self
+    }
+}
+
+#[cfg(feature = "rusb")]
+impl Default for OpenOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// # Async implementations
+///
+/// Async version of open/getserial functions.
+///
+/// Implemented by launching a thread, calling the sync function and passing the
+/// result back though a channel.
+/// This is not the most efficient implementation but it keeps the lib executor-independent
+/// and these functions are only one-time-call in most programs.
+#[cfg(feature = "async")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+impl Crazyradio {
+    /// Async vesion of [Crazyradio::open_first()]
+    pub async fn open_first_async() -> Result<Self> {
+        let (tx, rx) = flume::bounded(0);
+
+        std::thread::spawn(move || {
+            let _ = tx.send(Self::open_first());
+        });
+
+        rx.recv_async().await.unwrap()
+    }
+
+    /// Async vesion of [Crazyradio::open_nth()]
+    pub async fn open_nth_async(nth: usize) -> Result<Self> {
+        let (tx, rx) = flume::bounded(0);
+
+        std::thread::spawn(move || {
+            let _ = tx.send(Self::open_nth(nth));
+        });
+
+        rx.recv_async().await.unwrap()
+    }
+
+    /// Async vesion of [Crazyradio::open_by_serial()]
+    pub async fn open_by_serial_async(serial: &str) -> Result<Self> {
+        let serial = serial.to_owned();
+
+        let (tx, rx) = flume::bounded(0);
+
+        std::thread::spawn(move || {
+            let _ = tx.send(Self::open_by_serial(&serial));
+        });
+
+        rx.recv_async().await.unwrap()
+    }
+
+    /// Async vesion of [Crazyradio::reconnect()]
+    pub async fn reconnect_async(
+        serial: &str,
+        config: &RadioConfig,
+        backoff: Backoff,
+    ) -> Result<Self> {
+        let serial = serial.to_owned();
+        let config = *config;
+
+        let (tx, rx) = flume::bounded(0);
+
+        std::thread::spawn(move || {
+            let _ = tx.send(Self::reconnect(&serial, &config, backoff));
+        });
+
+        rx.recv_async().await.unwrap()
+    }
+
+    /// Async vesion of [Crazyradio::list_serials()]
+    pub async fn list_serials_async() -> Result<Vec<String>> {
+        let (tx, rx) = flume::bounded(0);
+
+        std::thread::spawn(move || {
+            let _ = tx.send(Self::list_serials());
+        });
+
+        rx.recv_async().await.unwrap()
+    }
+
+    /// Async vesion of [Crazyradio::scan_channels()]
+    ///
+    /// The scan runs in a thread so the radio has to be moved into it. It is
+    /// returned alongside the scan result, even if the scan failed.
+    ///
+    /// If the radio needs to be used concurrently from multiple tasks, the
+    /// [SharedCrazyradio] has an async scan function that does not require to
+    /// give away the radio.
+    pub async fn scan_channels_async(
+        self,
+        start: Channel,
+        stop: Channel,
+        packet: Vec<u8>,
+    ) -> (Self, Result<Vec<Channel>>) {
+        let (tx, rx) = flume::bounded(0);
+
+        std::thread::spawn(move || {
+            let mut cr = self;
+            let result = cr.scan_channels(start, stop, &packet);
+            // Ignore the error if the future has been dropped since, the radio is closed
+            let _ = tx.send((cr, result));
+        });
+
+        rx.recv_async().await.unwrap()
+    }
+}
+
+// The UsbError variant exists with and without the rusb feature so that matching on Error
+// does not depend on the features enabled by other crates
+#[cfg(feature = "rusb")]
+type UsbBackendError = rusb::Error;
+#[cfg(not(feature = "rusb"))]
+type UsbBackendError = core::convert::Infallible;
+
+#[derive(thiserror::Error, Debug, Clone)]
+pub enum Error {
+    /// Never returned without the **rusb** feature
+    #[error("Usb Error: {0}:?")]
+    UsbError(UsbBackendError),
+    #[error("Crazyradio not found")]
+    NotFound,
+    #[error("Invalid arguments")]
+    InvalidArgument,
+    #[error("Crazyradio version not supported")]
+    DongleVersionNotSupported,
+    #[error("Link down, too many consecutive packets not acked")]
+    LinkDown,
+    #[error("No ack received")]
+    NoAck,
+    #[error("Operation timed out")]
+    Timeout,
+    #[error("Crazyradio disconnected")]
+    Disconnected,
+    #[error("Ack payload length is not the expected one")]
+    AckLengthMismatch,
+    #[error("Packet only partially written to the Crazyradio")]
+    PartialWrite,
+    #[error("Device is not a Crazyradio")]
+    NotACrazyradio,
+    #[error("USB transport error: {0}")]
+    Transport(String),
+}
+
+#[cfg(feature = "rusb")]
+impl From<rusb::Error> for Error {
+    fn from(usb_error: rusb::Error) -> Self {
+        match usb_error {
+            // The device has been unplugged, or its endpoints are not responding anymore
+            rusb::Error::NoDevice | rusb::Error::Pipe => Error::Disconnected,
+            _ => Error::UsbError(usb_error),
+        }
+    }
+}
+
+/// Information about a connected Crazyradio
+///
+/// Returned by [Crazyradio::list_devices()].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RadioInfo {
+    /// Serial number, None if it cannot be read
+    ///
+    /// The serial number is read from the device, which fails if the radio
+    /// cannot be opened, for example when it has no usable driver.
+    pub serial: Option<String>,
+    /// Firmware version as (major, minor, sub-minor)
+    pub firmware_version: (u8, u8, u8),
+    /// Number of the USB bus the radio is connected to
+    pub bus_number: u8,
+    /// Address of the radio on the USB bus
+    pub address: u8,
+    /// Chain of USB hub port numbers from the root hub to the radio
+    ///
+    /// Unlike the address, this identifies the physical USB port the radio
+    /// is plugged in and does not change when the radio is plugged back in
+    /// the same port. On Linux, it corresponds to the sysfs device path
+    /// `<bus_number>-<port_numbers joined by '.'>`.
+    pub port_numbers: Vec<u8>,
+}
+
+/// USB interface information of a Crazyradio
+///
+/// Returned by [Crazyradio::interface_info()].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterfaceInfo {
+    /// USB specification version (bcdUSB) as (major, minor, sub-minor)
+    pub usb_version: (u8, u8, u8),
+    /// Number of the interface
+    pub interface_number: u8,
+    /// Endpoints of the interface
+    pub endpoints: Vec<EndpointInfo>,
+}
+
+/// USB endpoint information
+///
+/// Part of [InterfaceInfo].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct EndpointInfo {
+    /// Endpoint address, the bit 7 is set for IN endpoints
+    pub address: u8,
+    /// Maximum packet size of the endpoint
+    pub max_packet_size: u16,
+}
+
+/// Snapshot of the settings of a Crazyradio
+///
+/// Returned by [Crazyradio::state()] and [Crazyradio::reset_saving()] and
+/// applied back with [Crazyradio::restore()].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RadioState {
+    channel: Channel,
+    address: [u8; 5],
+    datarate: Datarate,
+    power: Power,
+    arc: usize,
+    ard: u16,
+    ack_enable: bool,
+}
+
+/// Radio configuration: channel, datarate, address and power
+///
+/// A plain value, independent of any opened radio, that can be copied to
+/// multiple threads and applied to a radio with [Crazyradio::apply_config()].
+/// The default configuration is the dongle boot configuration.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct RadioConfig {
+    /// Radio channel
+    pub channel: Channel,
+    /// Radio datarate
+    pub datarate: Datarate,
+    /// Radio address
+    pub address: [u8; 5],
+    /// Transmit power
+    pub power: Power,
+}
+
+impl Default for RadioConfig {
+    fn default() -> Self {
+        RadioConfig {
+            channel: Channel(2),
+            datarate: Datarate::Dr2M,
+            address: [0xe7; 5],
+            power: Power::P0dBm,
+        }
+    }
+}
+
+/// Retry policy used to reconnect a Crazyradio
+///
+/// Used by [Crazyradio::reconnect()]. The delay between two attempts starts at
+/// `initial` and is doubled after each failed attempt, up to `max`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Backoff {
+    /// Delay after the first failed attempt
+    pub initial: Duration,
+    /// Maximum delay between two attempts
+    pub max: Duration,
+    /// Number of attempts before giving up
+    pub attempts: usize,
+}
+
+impl Backoff {
+    /// Delay to wait after the failed attempt number `attempt`, starting at 0
+    pub fn delay(&self, attempt: usize) -> Duration {
+        let factor = 1u32.checked_shl(attempt as u32).unwrap_or(u32::MAX);
+        self.initial.saturating_mul(factor).min(self.max)
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff {
+            initial: Duration::from_millis(100),
+            max: Duration::from_secs(5),
+            attempts: 10,
+        }
+    }
+}
+
+/// Identifier of a physical Crazyradio dongle
+///
+/// Returned by [Crazyradio::id()]. The dongle is identified by its serial
+/// number which does not change when it is plugged in another USB port.
+/// The firmware version is kept for information.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct RadioId {
+    serial: String,
+    firmware_version: (u8, u8, u8),
+}
+
+impl RadioId {
+    /// Serial number of the dongle
+    pub fn serial(&self) -> &str {
+        &self.serial
+    }
+
+    /// Firmware version of the dongle as (major, minor, sub-minor)
+    pub fn firmware_version(&self) -> (u8, u8, u8) {
+        self.firmware_version
+    }
+}
+
+/// Ack status of a sent packet
+#[derive(Debug, Copy, Clone)]
+pub struct Ack {
+    /// At true if an ack packet has been received
+    pub received: bool,
+    /// Value of the nRF24 power detector when receiving the ack packet
+    ///
+    /// The nRF24 power detector (RPD) is set when a signal stronger than
+    /// -64dBm has been received on the channel. The Crazyradio firmware always
+    /// reports it and has no setting to disable it.
+    ///
+    /// This is the only access to the power detector: the firmware has no USB
+    /// request to put the nRF24 in receive mode and sample it without sending
+    /// a packet, so the energy on a channel cannot be sensed before talking.
+    pub power_detector: bool,
+    /// Number of time the packet was sent before an ack was received
+    pub retry: usize,
+    /// Length of the ack payload
+    ///
+    /// This is the length received using the nRF24 dynamic payload length
+    /// feature, which is always enabled in the Crazyradio.
+    pub length: usize,
+}
+
+impl Ack {
+    /// Decode the status byte sent by the Crazyradio before the ack payload
+    ///
+    /// The status byte bits are:
+    ///  - bit 0: ack received
+    ///  - bit 1: power detector
+    ///  - bits 4 to 7: number of retries
+    ///
+    /// `length` is the length of the ack payload.
+    pub fn from_status_byte(status: u8, length: usize) -> Ack {
+        Ack {
+            received: status & 0x01 != 0,
+            power_detector: status & 0x02 != 0,
+            retry: ((status & 0xf0) >> 4) as usize,
+            length,
+        }
+    }
+
+    /// Encode the ack status as the status byte sent by the Crazyradio
+    ///
+    /// See [Ack::from_status_byte()]. The number of retries is limited to 15.
+    pub fn to_status_byte(&self) -> u8 {
+        (self.received as u8)
+            | ((self.power_detector as u8) << 1)
+            | ((self.retry.min(15) as u8) << 4)
+    }
+
+    /// Convert the ack to a result, returning [Error::NoAck] if no ack has been received
+    ///
+    /// This allows to treat a missing ack as an error with `?`:
+    /// ```no_run
+    /// # use crazyradio::Crazyradio;
+    /// # #[cfg(feature = "rusb")]
+    /// # fn main() -> Result<(), crazyradio::Error> {
+    /// # let mut cr = Crazyradio::open_first()?;
+    /// let mut ack_data = [0u8; Crazyradio::MAX_ACK_LEN];
+    /// let ack = cr.send_packet(&[0xff], &mut ack_data)?.ok_or_timeout()?;
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "rusb"))]
+    /// # fn main() {}
+    /// ```
+    pub fn ok_or_timeout(self) -> Result<Ack> {
+        if self.received {
+            Ok(self)
+        } else {
+            Err(Error::NoAck)
+        }
+    }
+}
+
+/// Channel found by a scan at all the datarates
+///
+/// Returned by [Crazyradio::scan_all()].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ScanFound {
+    /// Channel where an ack has been received
+    pub channel: Channel,
+    /// Datarate with the best ack on this channel
+    pub datarate: Datarate,
+    /// Number of retries needed to get the ack at this datarate
+    pub retry: usize,
+}
+
+/// Statistics about a sent packet
+///
+/// Returned by [Crazyradio::send_packet_stats()].
+#[derive(Debug, Copy, Clone)]
+pub struct PacketStats {
+    /// Ack status of the packet
+    pub ack: Ack,
+    /// Number of time the packet was sent before an ack was received
+    pub retry: usize,
+    /// Value of the nRF24 power detector when receiving the ack packet
+    pub power_detector: bool,
+    /// Time measured between the start of the USB write and the end of the USB read of the ack
+    pub round_trip: Duration,
+}
+
+/// Result of a throughput measurement
+///
+/// Returned by [Crazyradio::measure_throughput()].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Throughput {
+    /// Number of packets sent
+    pub packets: u64,
+    /// Number of packets acked
+    pub acked: u64,
+    /// Number of bytes sent and received in ack payloads
+    pub bytes: u64,
+    /// Duration of the measurement
+    pub elapsed: Duration,
+}
+
+impl Throughput {
+    /// Number of packets sent per second
+    pub fn packets_per_second(&self) -> f64 {
+        self.packets as f64 / self.elapsed.as_secs_f64()
+    }
+
+    /// Number of bytes sent and received per second
+    pub fn bytes_per_second(&self) -> f64 {
+        self.bytes as f64 / self.elapsed.as_secs_f64()
+    }
+}
+
+/// Settings cache statistics
+///
+/// Returned by [Crazyradio::cache_stats()]. A hit is a setting not sent to
+/// the dongle because it was already set, a miss is a setting sent to the
+/// dongle. Settings sent while caching is disabled are counted as misses.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Number of channel settings skipped
+    pub channel_hits: u64,
+    /// Number of channel settings sent
+    pub channel_misses: u64,
+    /// Number of datarate settings skipped
+    pub datarate_hits: u64,
+    /// Number of datarate settings sent
+    pub datarate_misses: u64,
+    /// Number of address settings skipped
+    pub address_hits: u64,
+    /// Number of address settings sent
+    pub address_misses: u64,
+}
+
+/// Link statistics
+///
+/// Returned by [Crazyradio::link_stats()].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct LinkStats {
+    /// Number of packets sent
+    pub sent: u64,
+    /// Number of packets acked
+    pub acked: u64,
+    /// Total number of hardware retries
+    pub retries: u64,
+    /// Length of the longest ack payload received
+    pub max_ack_length: usize,
+}
+
+impl LinkStats {
+    /// Ratio of packets sent that have not been acked, between 0 and 1
+    ///
+    /// Returns 0 if no packet has been sent.
+    pub fn loss_ratio(&self) -> f64 {
+        if self.sent == 0 {
+            0.0
+        } else {
+            (self.sent - self.acked) as f64 / self.sent as f64
+        }
+    }
+}
+
+/// Radio channel
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde_support", derive(Serialize))]
+pub struct Channel(u8);
+
+#[cfg(feature = "serde_support")]
+impl<'de> Deserialize<'de> for Channel {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Channel, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let ch_number: u8 = Deserialize::deserialize(deserializer)?;
+        let channel = Channel::from_number(ch_number)
+            .map_err(|e| serde::de::Error::custom(format!("{:?}", e)))?;
+        Ok(channel)
+    }
+}
+
+impl Channel {
+    pub fn from_number(channel: u8) -> Result<Self> {
+        if channel < 126 {
+            Ok(Channel(channel))
+        } else {
+            Err(Error::InvalidArgument)
+        }
+    }
+
+    /// Create a channel without checking that the number is a valid channel.
+    ///
+    /// Valid channels are between 0 and 125, this is not verified by this
+    /// function. It is intended to be used in const context and in tests, for
+    /// example to build the expected channel to compare with the result of a scan.
+    /// [Channel::from_number()] should be used otherwise.
+    pub const fn new_unchecked(channel: u8) -> Self {
+        Channel(channel)
+    }
+
+    /// Return the channel number
+    pub const fn number(&self) -> u8 {
+        self.0
+    }
+
+    /// Iterate over all the channels in an order optimized to find devices quickly
+    ///
+    /// Channel 80, the default channel of the Crazyflie, comes first followed
+    /// by all the other channels in increasing order. Used by
+    /// [Crazyradio::scan_channels_ordered()].
+    pub fn scan_order() -> impl Iterator<Item = Channel> {
+        const DEFAULT_CHANNEL: u8 = 80;
+
+        std::iter::once(Channel(DEFAULT_CHANNEL))
+            .chain((0..126).filter(|&ch| ch != DEFAULT_CHANNEL).map(Channel))
+    }
+}
+
+impl From<Channel> for u8 {
+    fn from(val: Channel) -> Self {
+        val.0
+    }
+}
+
+/// Range of radio channels
+///
+/// Both the start and the stop channels are part of the range.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde_support", derive(Serialize))]
+pub struct ChannelRange {
+    start: Channel,
+    stop: Channel,
+}
+
+#[cfg(feature = "serde_support")]
+impl<'de> Deserialize<'de> for ChannelRange {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<ChannelRange, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Range {
+            start: Channel,
+            stop: Channel,
+        }
+
+        let range: Range = Deserialize::deserialize(deserializer)?;
+        let range = ChannelRange::new(range.start, range.stop)
+            .map_err(|e| serde::de::Error::custom(format!("{:?}", e)))?;
+        Ok(range)
+    }
+}
+
+impl ChannelRange {
+    /// Create a range from `start` to `stop` included
+    ///
+    /// Returns [Error::InvalidArgument] if `start` is greater than `stop`.
+    pub fn new(start: Channel, stop: Channel) -> Result<Self> {
+        if start.0 <= stop.0 {
+            Ok(ChannelRange { start, stop })
+        } else {
+            Err(Error::InvalidArgument)
+        }
+    }
+
+    /// First channel of the range
+    pub fn start(&self) -> Channel {
+        self.start
+    }
+
+    /// Last channel of the range
+    pub fn stop(&self) -> Channel {
+        self.stop
+    }
+
+    /// Iterate over all the channels of the range
+    pub fn iter(&self) -> impl Iterator<Item = Channel> {
+        (self.start.0..=self.stop.0).map(Channel)
+    }
+}
+
+/// Radio datarate
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub enum Datarate {
+    Dr250K = 0,
+    Dr1M = 1,
+    Dr2M = 2,
+}
+
+impl Datarate {
+    /// Over-the-air time of a packet sent at this datarate
+    ///
+    /// Computed from the nRF24 Enhanced ShockBurst packet format: 1 byte of
+    /// preamble, `address_width` bytes of address, 9 bits of packet control
+    /// field, `payload_len` bytes of payload and 2 bytes of CRC. This does
+    /// not include the radio settling time before the transmission.
+    pub fn airtime(&self, payload_len: usize, address_width: u8) -> Duration {
+        let bits = 8 + 8 * address_width as u64 + 9 + 8 * payload_len as u64 + 16;
+        let ns_per_bit = match self {
+            Datarate::Dr250K => 4000,
+            Datarate::Dr1M => 1000,
+            Datarate::Dr2M => 500,
+        };
+        Duration::from_nanos(bits * ns_per_bit)
+    }
+}
+
+/// Time to wait for an ack packet
+///
+/// Used with [Crazyradio::set_ack_timeout()].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AckTimeout {
+    /// Fixed wait time, see [Crazyradio::set_ard_time()]
+    Time(Duration),
+    /// Time needed to receive an ack payload of up to this number of bytes
+    /// at the current datarate, see [Crazyradio::set_ard_bytes()]
+    Bytes(u8),
+}
+
+/// Radio power
+///
+/// Powers are ordered from the lowest to the highest, this allows to clamp
+/// a power with `min()` and `max()`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub enum Power {
+    Pm18dBm = 0,
+    Pm12dBm = 1,
+    Pm6dBm = 2,
+    P0dBm = 3,
+}
+
+impl Power {
+    /// Create a power from its value in dBm
+    ///
+    /// Only the nRF24 power levels, -18, -12, -6 and 0dBm, are accepted.
+    /// Returns [Error::InvalidArgument] for other values.
+    pub fn from_dbm(dbm: i8) -> Result<Self> {
+        match dbm {
+            -18 => Ok(Power::Pm18dBm),
+            -12 => Ok(Power::Pm12dBm),
+            -6 => Ok(Power::Pm6dBm),
+            0 => Ok(Power::P0dBm),
+            _ => Err(Error::InvalidArgument),
+        }
+    }
+
+    /// Output power of the nRF24 radio in dBm
+    pub fn dbm(&self) -> i8 {
+        match self {
+            Power::Pm18dBm => -18,
+            Power::Pm12dBm => -12,
+            Power::Pm6dBm => -6,
+            Power::P0dBm => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "rusb")]
+    use super::{duplicated_serials, group_by_hub, RadioInfo};
+    use super::{
+        Ack, AckTimeout, Backoff, Channel, ChannelRange, Crazyradio, Datarate, Error, LinkStats,
+        Power, RadioConfig, ScanFound, UsbTransport,
+    };
+    use core::time::Duration;
+    use std::collections::VecDeque;
+    use std::time::Instant;
+
+    // Transport recording the control requests and answering bulk reads with a fixed ack
+    #[derive(Default)]
+    struct MockTransport {
+        control_requests: Vec<(u8, u16)>,
+        ack: Vec<u8>,
+        short_write: bool,
+        // Ack each packet with its own data instead of `ack`
+        echo: bool,
+        echoed: VecDeque<Vec<u8>>,
+        // Number of bulk reads timing out before answering
+        in_timeouts: usize,
+        packets_sent: usize,
+    }
+
+    impl UsbTransport for MockTransport {
+        type Error = Error;
+
+        fn control_out(
+            &mut self,
+            request: u8,
+            value: u16,
+            _index: u16,
+            data: &[u8],
+            _timeout: Duration,
+        ) -> crate::Result<usize> {
+            self.control_requests.push((request, value));
+            Ok(data.len())
+        }
+
+        fn bulk_out(
+            &mut self,
+            _endpoint: u8,
+            data: &[u8],
+            _timeout: Duration,
+        ) -> crate::Result<usize> {
+            self.packets_sent += 1;
+            if self.echo {
+                self.echoed.push_back(data.to_vec());
+            }
+            if self.short_write {
+                Ok(data.len() - 1)
+            } else {
+                Ok(data.len())
+            }
+        }
+
+        fn bulk_in(
+            &mut self,
+            _endpoint: u8,
+            buffer: &mut [u8],
+            _timeout: Duration,
+        ) -> crate::Result<usize> {
+            if self.in_timeouts > 0 {
+                self.in_timeouts -= 1;
+                return Err(Error::Timeout);
+            }
+            if self.echo {
+                let data = self.echoed.pop_front().ok_or(Error::Timeout)?;
+                buffer[0] = 0x01;
+                buffer[1..data.len() + 1].copy_from_slice(&data);
+                return Ok(data.len() + 1);
+            }
+            buffer[..self.ack.len()].copy_from_slice(&self.ack);
+            Ok(self.ack.len())
+        }
+    }
+
+    #[test]
+    fn test_that_new_unchecked_channel_equals_checked_channel() {
+        const CHANNEL: Channel = Channel::new_unchecked(42);
+
+        assert_eq!(Channel::from_number(42).unwrap(), CHANNEL);
+    }
+
+    #[test]
+    fn test_that_channel_number_returns_the_channel_number() {
+        let channel = Channel::from_number(80).unwrap();
+
+        assert_eq!(channel.number(), 80);
+    }
+
+    #[test]
+    fn test_that_scan_order_yields_each_channel_once_starting_with_80() {
+        let mut channels: Vec<u8> = Channel::scan_order().map(|ch| ch.number()).collect();
+
+        assert_eq!(channels[0], 80);
+        channels.sort_unstable();
+        assert_eq!(channels, (0..126).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn test_that_power_is_ordered_by_dbm() {
+        assert!(Power::Pm18dBm < Power::Pm12dBm);
+        assert!(Power::Pm6dBm < Power::P0dBm);
+        assert_eq!(Power::P0dBm.min(Power::Pm6dBm), Power::Pm6dBm);
+        assert_eq!(Power::Pm12dBm.dbm(), -12);
+    }
+
+    #[test]
+    fn test_that_channel_range_with_start_after_stop_is_rejected() {
+        let result = ChannelRange::new(Channel(80), Channel(2));
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_that_channel_range_iterates_all_channels() {
+        let range = ChannelRange::new(Channel(10), Channel(12)).unwrap();
+
+        let channels: Vec<Channel> = range.iter().collect();
+
+        assert_eq!(channels, vec![Channel(10), Channel(11), Channel(12)]);
+    }
+
+    #[test]
+    fn test_that_a_radio_created_from_a_transport_is_reset_to_boot_values() {
+        let cr = Crazyradio::from_transport(MockTransport::default()).unwrap();
+
+        assert_eq!(
+            cr.transport.control_requests,
+            vec![
+                (0x03, 2),
+                (0x01, 2),
+                (0x20, 0),
+                (0x02, 0),
+                (0x04, 3),
+                (0x06, 3),
+                (0x05, 0x80 | 32),
+                (0x10, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_that_the_ack_status_and_payload_are_parsed() {
+        let mut cr = Crazyradio::from_transport(MockTransport {
+            ack: vec![0x33, 0xaa, 0xbb],
+            ..Default::default()
+        })
+        .unwrap();
+        let mut ack_data = [0u8; 32];
+
+        let ack = cr.send_packet(&[0xff], &mut ack_data).unwrap();
+
+        assert!(ack.received);
+        assert!(ack.power_detector);
+        assert_eq!(ack.retry, 3);
+        assert_eq!(ack.length, 2);
+        assert_eq!(&ack_data[..2], &[0xaa, 0xbb]);
+    }
+
+    #[test]
+    fn test_that_verify_echo_compares_the_ack_payload_with_the_pattern() {
+        let mut cr = Crazyradio::from_transport(MockTransport {
+            ack: vec![0x01, 0x12, 0x34],
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert!(cr.verify_echo(&[0x12, 0x34]).unwrap());
+        assert!(!cr.verify_echo(&[0x12, 0x35]).unwrap());
+        assert!(!cr.verify_echo(&[0x12]).unwrap());
+        assert!(cr.verify_echo(&[0x12, 0x34, 0x56]).unwrap());
+        assert!(!cr.verify_echo(&[0x13, 0x34, 0x56]).unwrap());
+
+        cr.transport_mut().ack = vec![0x01];
+        assert!(!cr.verify_echo(&[0x12, 0x34]).unwrap());
+    }
+
+    #[test]
+    fn test_that_the_carrier_is_enabled_after_setting_channel_and_power() {
+        let mut cr = Crazyradio::from_transport(MockTransport::default()).unwrap();
+        cr.transport.control_requests.clear();
+
+        cr.start_carrier(Channel(42), Power::Pm6dBm).unwrap();
+
+        assert_eq!(
+            cr.transport.control_requests,
+            vec![(0x01, 42), (0x04, 2), (0x20, 1)]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "rusb")]
+    fn test_that_radios_are_grouped_by_hub() {
+        let radio = |bus_number, port_numbers: &[u8]| RadioInfo {
+            serial: None,
+            firmware_version: (0, 5, 3),
+            bus_number,
+            address: 0,
+            port_numbers: port_numbers.to_vec(),
+        };
+
+        let groups = group_by_hub(vec![
+            radio(1, &[1, 2]),
+            radio(1, &[3]),
+            radio(1, &[1, 4]),
+            radio(2, &[1, 2]),
+            radio(1, &[4]),
+        ]);
+
+        assert_eq!(
+            groups,
+            vec![
+                vec![radio(1, &[1, 2]), radio(1, &[1, 4])],
+                vec![radio(1, &[3]), radio(1, &[4])],
+                vec![radio(2, &[1, 2])],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_that_reacquire_returns_the_first_acking_channel() {
+        let mut cr = Crazyradio::from_transport(MockTransport {
+            ack: vec![0x01],
+            ..Default::default()
+        })
+        .unwrap();
+        let range = ChannelRange::new(Channel(10), Channel(20)).unwrap();
+
+        let channel = cr
+            .reacquire(&[0xe7; 5], Datarate::Dr1M, range, &[0xff])
+            .unwrap();
+
+        assert_eq!(channel, Some(Channel(10)));
+        assert_eq!(cr.channel(), Channel(10));
+        assert_eq!(cr.datarate(), Datarate::Dr1M);
+    }
+
+    #[test]
+    fn test_that_scan_channels_with_address_restores_the_address() {
+        let mut cr = Crazyradio::from_transport(MockTransport {
+            ack: vec![0x01],
+            ..Default::default()
+        })
+        .unwrap();
+        let range = ChannelRange::new(Channel(10), Channel(12)).unwrap();
+        cr.set_address([0x01, 0x02, 0x03, 0x04, 0x05]).unwrap();
+
+        let found = cr
+            .scan_channels_with_address(range, [0xe7; 5], &[0xff])
+            .unwrap();
+
+        assert_eq!(found, vec![Channel(10), Channel(11), Channel(12)]);
+        assert_eq!(cr.address(), [0x01, 0x02, 0x03, 0x04, 0x05]);
+    }
+
+    #[test]
+    fn test_that_best_channel_keeps_the_first_candidate_on_equal_acks() {
+        let mut cr = Crazyradio::from_transport(MockTransport {
+            ack: vec![0x13],
+            ..Default::default()
+        })
+        .unwrap();
+
+        let channel = cr
+            .best_channel(
+                &[Channel(12), Channel(10), Channel(11)],
+                &[0xe7; 5],
+                Datarate::Dr2M,
+                &[0xff],
+            )
+            .unwrap();
+
+        assert_eq!(channel, Some(Channel(12)));
+        assert_eq!(cr.channel(), Channel(12));
+    }
+
+    #[test]
+    fn test_that_send_packet_oneshot_restores_the_link() {
+        let mut cr = Crazyradio::from_transport(MockTransport {
+            ack: vec![0x01],
+            ..Default::default()
+        })
+        .unwrap();
+        cr.set_channel(Channel(10)).unwrap();
+        cr.transport.control_requests.clear();
+        let mut ack_data = [0u8; 32];
+
+        let ack = cr
+            .send_packet_oneshot(
+                Channel(20),
+                [0xe7; 5],
+                Datarate::Dr2M,
+                &[0xff],
+                &mut ack_data,
+            )
+            .unwrap();
+
+        assert!(ack.received);
+        assert_eq!(cr.channel(), Channel(10));
+        // Only the channel differs from the current link
+        assert_eq!(cr.transport.control_requests, vec![(0x01, 20), (0x01, 10)]);
+    }
+
+    #[test]
+    fn test_that_broadcast_to_default_sends_without_ack_to_the_broadcast_address() {
+        let mut cr = Crazyradio::from_transport(MockTransport::default()).unwrap();
+        cr.transport.control_requests.clear();
+
+        cr.broadcast_to_default(Channel(80), &[0xff]).unwrap();
+
+        assert_eq!(cr.address(), [0xff, 0xe7, 0xe7, 0xe7, 0xe7]);
+        assert_eq!(
+            cr.transport.control_requests,
+            vec![(0x01, 80), (0x02, 0), (0x10, 0), (0x10, 1)]
+        );
+    }
+
+    #[test]
+    fn test_that_scan_until_gives_up_after_the_timeout() {
+        let mut cr = Crazyradio::from_transport(MockTransport {
+            ack: vec![0x00],
+            ..Default::default()
+        })
+        .unwrap();
+        let range = ChannelRange::new(Channel(10), Channel(20)).unwrap();
+
+        let found = cr
+            .scan_until(range, &[0xff], Duration::from_millis(10))
+            .unwrap();
+
+        assert_eq!(found, None);
+    }
+
+    #[test]
+    fn test_that_the_cont_carrier_state_follows_start_and_stop_carrier() {
+        let mut cr = Crazyradio::from_transport(MockTransport::default()).unwrap();
+        assert!(!cr.cont_carrier());
+
+        cr.start_carrier(Channel(80), Power::Pm18dBm).unwrap();
+        assert!(cr.cont_carrier());
+
+        cr.stop_carrier().unwrap();
+        assert!(!cr.cont_carrier());
+    }
+
+    #[test]
+    fn test_that_auto_ard_follows_the_datarate() {
+        let mut cr = Crazyradio::from_transport(MockTransport::default()).unwrap();
+        cr.transport.control_requests.clear();
+
+        cr.set_auto_ard(true).unwrap();
+        cr.set_datarate(Datarate::Dr250K).unwrap();
+
+        // 500us at 2M then 1500us at 250K
+        assert_eq!(
+            cr.transport.control_requests,
+            vec![(0x05, 1), (0x03, 0), (0x05, 5)]
+        );
+    }
+
+    #[test]
+    fn test_that_link_stats_are_reset_on_channel_change_when_enabled() {
+        let mut cr = Crazyradio::from_transport(MockTransport {
+            ack: vec![0x01],
+            ..Default::default()
+        })
+        .unwrap();
         let mut ack_data = [0u8; 32];
-        let mut result: Vec<Channel> = vec![];
-        for ch in start.0..stop.0 + 1 {
-            let channel = Channel::from_number(ch).unwrap();
-            self.set_channel(channel)?;
-            let ack = self.send_packet(packet, &mut ack_data)?;
-            if ack.received {
-                result.push(channel);
-            }
-        }
-        Ok(result)
+        cr.set_auto_reset_stats(true);
+
+        cr.send_packet(&[0xff], &mut ack_data).unwrap();
+        cr.set_channel(cr.channel()).unwrap();
+        assert_eq!(cr.link_stats().sent, 1);
+
+        cr.set_channel(Channel(80)).unwrap();
+        assert_eq!(cr.link_stats().sent, 0);
     }
 
-    /// Launch the bootloader.
-    ///
-    /// Consumes the Crazyradio since it is not usable after that (it is in bootlaoder mode ...).
-    pub fn launch_bootloader(self) -> Result<()> {
-        self.device_handle.write_control(
-            0x40,
-            UsbCommand::LaunchBootloader as u8,
-            0,
-            0,
-            &[],
-            Duration::from_secs(1),
-        )?;
-        Ok(())
+    #[test]
+    fn test_that_send_sequence_returns_the_number_of_packets_acked() {
+        let mut cr = Crazyradio::from_transport(MockTransport {
+            ack: vec![0x01],
+            ..Default::default()
+        })
+        .unwrap();
+        let packets: [&[u8]; 3] = [&[0x01], &[0x02], &[0x03]];
+
+        assert_eq!(cr.send_sequence(&packets).unwrap(), 3);
+
+        cr.transport.ack = vec![0x00];
+        assert_eq!(cr.send_sequence(&packets).unwrap(), 0);
     }
 
-    /// Set the radio in continious carrier mode.
-    ///
-    /// In continious carrier mode, the radio will transmit a continious sine
-    /// wave at the setup channel frequency using the setup transmit power.
-    pub fn set_cont_carrier(&mut self, enable: bool) -> Result<()> {
-        self.device_handle.write_control(
-            0x40,
-            UsbCommand::SetContCarrier as u8,
-            enable as u16,
-            0,
-            &[],
-            Duration::from_secs(1),
-        )?;
-        Ok(())
+    #[test]
+    fn test_that_scan_channels_iter_only_scans_the_channels_consumed() {
+        let mut cr = Crazyradio::from_transport(MockTransport {
+            ack: vec![0x01],
+            ..Default::default()
+        })
+        .unwrap();
+        let range = ChannelRange::new(Channel(10), Channel(20)).unwrap();
+
+        let found: Vec<Channel> = cr
+            .scan_channels_iter(range, &[0xff])
+            .take(2)
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(found, vec![Channel(10), Channel(11)]);
+        assert_eq!(cr.channel(), Channel(11));
     }
 
-    /// Send a data packet and receive an ack packet.
-    ///
-    /// # Arguments
-    ///
-    ///  * `data`: Up to 32 bytes of data to be send.
-    ///  * `ack_data`: Buffer to hold the data received from the ack packet
-    ///                payload. The ack payload can be up to 32 bytes, if this
-    ///                buffer length is lower than 32 bytes the ack data might
-    ///                be truncated. The length of the ack payload is returned
-    ///                in Ack::length.
-    pub fn send_packet(&mut self, data: &[u8], ack_data: &mut [u8]) -> Result<Ack> {
-        self.device_handle
-            .write_bulk(0x01, data, Duration::from_secs(1))?;
-        let mut received_data = [0u8; 33];
-        let received =
-            self.device_handle
-                .read_bulk(0x81, &mut received_data, Duration::from_secs(1))?;
-
-        if ack_data.len() <= 32 {
-            ack_data.copy_from_slice(&received_data[1..ack_data.len() + 1]);
-        } else {
-            ack_data
-                .split_at_mut(32)
-                .0
-                .copy_from_slice(&received_data[1..33]);
+    #[test]
+    fn test_that_the_setting_guard_restores_the_previous_channel() {
+        let mut cr = Crazyradio::from_transport(MockTransport::default()).unwrap();
+
+        {
+            let cr = cr.with_channel(Channel(80)).unwrap();
+            assert_eq!(cr.channel(), Channel(80));
         }
 
-        Ok(Ack {
-            received: received_data[0] & 0x01 != 0,
-            power_detector: received_data[0] & 0x02 != 0,
-            retry: ((received_data[0] & 0xf0) >> 4) as usize,
-            length: received - 1,
+        assert_eq!(cr.channel(), Channel(2));
+    }
+
+    #[test]
+    fn test_that_multicast_changes_the_channel_once_per_channel() {
+        let mut cr = Crazyradio::from_transport(MockTransport {
+            ack: vec![0x01],
+            ..Default::default()
         })
+        .unwrap();
+        cr.transport.control_requests.clear();
+
+        let targets = [
+            (Channel(80), [1; 5]),
+            (Channel(10), [2; 5]),
+            (Channel(80), [3; 5]),
+        ];
+        let acks = cr.multicast(&targets, Datarate::Dr2M, &[0xff]).unwrap();
+
+        let channel_changes: Vec<u16> = cr
+            .transport
+            .control_requests
+            .iter()
+            .filter(|(request, _)| *request == 0x01)
+            .map(|(_, value)| *value)
+            .collect();
+        assert_eq!(acks.len(), 3);
+        assert_eq!(channel_changes, vec![10, 80]);
     }
-}
 
-/// # Async implementations
-///
-/// Async version of open/getserial functions.
-///
-/// Implemented by launching a thread, calling the sync function and passing the
-/// result back though a channel.
-/// This is not the most efficient implementation but it keeps the lib executor-independent
-/// and these functions are only one-time-call in most programs.
-#[cfg(feature = "async")]
-#[cfg_attr(docsrs, doc(cfg(feature = "async")))]
-impl Crazyradio {
+    #[test]
+    #[cfg(feature = "rusb")]
+    fn test_that_a_missing_device_is_reported_as_disconnected() {
+        assert!(matches!(
+            Error::from(rusb::Error::NoDevice),
+            Error::Disconnected
+        ));
+        assert!(matches!(
+            Error::from(rusb::Error::Pipe),
+            Error::Disconnected
+        ));
+        assert!(matches!(
+            Error::from(rusb::Error::Timeout),
+            Error::UsbError(rusb::Error::Timeout)
+        ));
+    }
 
-    /// Async vesion of [Crazyradio::open_first()]
-    pub async fn open_first_async() -> Result<Self> {
-        let (tx, rx) = flume::bounded(0);
+    #[test]
+    fn test_that_the_default_config_is_the_boot_config() {
+        let cr = Crazyradio::from_transport(MockTransport::default()).unwrap();
 
-        std::thread::spawn(move || tx.send(Self::open_first()));
+        assert_eq!(cr.config(), RadioConfig::default());
+    }
 
-        rx.recv_async().await.unwrap()
+    #[test]
+    fn test_that_throughput_counts_sent_and_ack_payload_bytes() {
+        let mut cr = Crazyradio::from_transport(MockTransport {
+            ack: vec![0x01, 0xaa, 0xbb],
+            ..Default::default()
+        })
+        .unwrap();
+
+        let throughput = cr
+            .measure_throughput(&[0xff; 4], Duration::from_millis(10))
+            .unwrap();
+
+        assert!(throughput.packets > 0);
+        assert_eq!(throughput.acked, throughput.packets);
+        assert_eq!(throughput.bytes, throughput.packets * 6);
+        assert!(throughput.elapsed >= Duration::from_millis(10));
     }
 
-    /// Async vesion of [Crazyradio::open_nth()]
-    pub async fn open_nth_async(nth: usize) -> Result<Self> {
-        let (tx, rx) = flume::bounded(0);
+    #[test]
+    fn test_that_the_ack_of_a_timed_out_packet_is_not_returned_for_the_next_one() {
+        let mut cr = Crazyradio::from_transport(MockTransport {
+            echo: true,
+            in_timeouts: 1,
+            ..Default::default()
+        })
+        .unwrap();
+
+        let mut ack_data = [0u8; 32];
+        let deadline = Instant::now() + Duration::from_secs(10);
+        assert!(matches!(
+            cr.send_packet_deadline(&[0x01], &mut ack_data, deadline),
+            Err(Error::Timeout)
+        ));
+
+        let ack = cr.send_packet(&[0x02], &mut ack_data).unwrap();
+        assert_eq!(&ack_data[..ack.length], &[0x02]);
+    }
 
-        std::thread::spawn(move || tx.send(Self::open_nth(nth)));
+    #[test]
+    fn test_that_no_packet_is_sent_once_the_link_is_down() {
+        let mut cr = Crazyradio::from_transport(MockTransport {
+            ack: vec![0x00],
+            ..Default::default()
+        })
+        .unwrap();
+        cr.set_link_down_threshold(2);
 
-        rx.recv_async().await.unwrap()
+        let mut ack_data = [0u8; 32];
+        assert!(cr.send_packet_checked(&[0xff], &mut ack_data).is_ok());
+        assert!(matches!(
+            cr.send_packet_checked(&[0xff], &mut ack_data),
+            Err(Error::LinkDown)
+        ));
+        assert!(matches!(
+            cr.send_packet_checked(&[0xff], &mut ack_data),
+            Err(Error::LinkDown)
+        ));
+        assert_eq!(cr.transport().packets_sent, 2);
+
+        cr.transport_mut().ack = vec![0x01];
+        cr.send_packet(&[0xff], &mut ack_data).unwrap();
+        assert!(cr.send_packet_checked(&[0xff], &mut ack_data).is_ok());
     }
 
-    /// Async vesion of [Crazyradio::open_by_serial()]
-    pub async fn open_by_serial_async(serial: &str) -> Result<Self> {
-        let serial = serial.to_owned();
+    #[test]
+    fn test_that_an_unexpected_ack_length_is_an_error() {
+        let mut cr = Crazyradio::from_transport(MockTransport {
+            ack: vec![0x01, 0xaa, 0xbb],
+            ..Default::default()
+        })
+        .unwrap();
+        let mut ack_data = [0u8; 32];
 
-        let (tx, rx) = flume::bounded(0);
+        cr.set_expected_ack_length(Some(2));
+        assert!(cr.send_packet(&[0xff], &mut ack_data).is_ok());
 
-        std::thread::spawn(move || tx.send(Self::open_by_serial(&serial)));
+        cr.set_expected_ack_length(Some(3));
+        assert!(matches!(
+            cr.send_packet(&[0xff], &mut ack_data),
+            Err(Error::AckLengthMismatch)
+        ));
+    }
 
-        rx.recv_async().await.unwrap()
+    #[test]
+    fn test_that_the_backoff_delay_doubles_up_to_the_maximum() {
+        let backoff = Backoff {
+            initial: Duration::from_millis(100),
+            max: Duration::from_millis(500),
+            attempts: 10,
+        };
+
+        assert_eq!(backoff.delay(0), Duration::from_millis(100));
+        assert_eq!(backoff.delay(1), Duration::from_millis(200));
+        assert_eq!(backoff.delay(2), Duration::from_millis(400));
+        assert_eq!(backoff.delay(3), Duration::from_millis(500));
+        assert_eq!(backoff.delay(100), Duration::from_millis(500));
     }
 
-    /// Async vesion of [Crazyradio::list_serials()]
-    pub async fn list_serials_async() -> Result<Vec<String>> {
-        let (tx, rx) = flume::bounded(0);
+    #[test]
+    fn test_that_airtime_counts_the_packet_bits_at_the_datarate() {
+        // 8 + 5 * 8 + 9 + 32 * 8 + 16 = 329 bits
+        assert_eq!(Datarate::Dr2M.airtime(32, 5), Duration::from_nanos(164_500));
+        assert_eq!(Datarate::Dr1M.airtime(32, 5), Duration::from_micros(329));
+        assert_eq!(Datarate::Dr250K.airtime(32, 5), Duration::from_micros(1316));
+    }
+
+    #[test]
+    fn test_that_power_from_dbm_accepts_the_nrf24_levels_only() {
+        for power in [Power::Pm18dBm, Power::Pm12dBm, Power::Pm6dBm, Power::P0dBm] {
+            assert_eq!(Power::from_dbm(power.dbm()).unwrap(), power);
+        }
+        assert!(matches!(Power::from_dbm(-3), Err(Error::InvalidArgument)));
+        assert!(matches!(Power::from_dbm(4), Err(Error::InvalidArgument)));
+    }
 
-        std::thread::spawn(move || tx.send(Self::list_serials()));
+    #[test]
+    fn test_that_packets_longer_than_the_maximum_are_rejected() {
+        let mut cr = Crazyradio::from_transport(MockTransport {
+            ack: vec![0x01],
+            ..Default::default()
+        })
+        .unwrap();
+        let mut ack_data = [0u8; 32];
 
-        rx.recv_async().await.unwrap()
+        assert!(cr.send_packet(&[0xff; 32], &mut ack_data).is_ok());
+        assert!(matches!(
+            cr.send_packet(&[0xff; 33], &mut ack_data),
+            Err(Error::InvalidArgument)
+        ));
     }
-}
 
-#[derive(thiserror::Error, Debug, Clone)]
-pub enum Error {
-    #[error("Usb Error: {0}:?")]
-    UsbError(rusb::Error),
-    #[error("Crazyradio not found")]
-    NotFound,
-    #[error("Invalid arguments")]
-    InvalidArgument,
-    #[error("Crazyradio version not supported")]
-    DongleVersionNotSupported,
-}
+    #[test]
+    fn test_that_a_partial_write_is_reported() {
+        let mut cr = Crazyradio::from_transport(MockTransport {
+            ack: vec![0x01],
+            short_write: true,
+            ..Default::default()
+        })
+        .unwrap();
+        let mut ack_data = [0u8; 32];
 
-impl From<rusb::Error> for Error {
-    fn from(usb_error: rusb::Error) -> Self {
-        Error::UsbError(usb_error)
+        assert!(matches!(
+            cr.send_packet(&[0x01, 0x02], &mut ack_data),
+            Err(Error::PartialWrite)
+        ));
     }
-}
 
-/// Ack status of a sent packet
-#[derive(Debug, Copy, Clone)]
-pub struct Ack {
-    /// At true if an ack packet has been received
-    pub received: bool,
-    /// Value of the nRF24 power detector when receiving the ack packet
-    pub power_detector: bool,
-    /// Number of time the packet was sent before an ack was received
-    pub retry: usize,
-    /// Length of the ack payload
-    pub length: usize,
-}
+    #[test]
+    fn test_that_the_received_bit_of_the_status_byte_is_decoded() {
+        let ack = Ack::from_status_byte(0x01, 0);
 
-/// Radio channel
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
-#[cfg_attr(feature = "serde_support", derive(Serialize))]
-pub struct Channel(u8);
+        assert!(ack.received);
+        assert!(!ack.power_detector);
+        assert_eq!(ack.retry, 0);
+        assert_eq!(ack.to_status_byte(), 0x01);
+    }
 
-#[cfg(feature = "serde_support")]
-impl<'de> Deserialize<'de> for Channel {
-    fn deserialize<D>(deserializer: D) -> std::result::Result<Channel, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        let ch_number: u8 = Deserialize::deserialize(deserializer)?;
-        let channel = Channel::from_number(ch_number)
-            .map_err(|e| serde::de::Error::custom(format!("{:?}", e)))?;
-        Ok(channel)
+    #[test]
+    fn test_that_the_power_detector_bit_of_the_status_byte_is_decoded() {
+        let ack = Ack::from_status_byte(0x02, 0);
+
+        assert!(!ack.received);
+        assert!(ack.power_detector);
+        assert_eq!(ack.retry, 0);
+        assert_eq!(ack.to_status_byte(), 0x02);
     }
-}
 
-impl Channel {
-    pub fn from_number(channel: u8) -> Result<Self> {
-        if channel < 126 {
-            Ok(Channel(channel))
-        } else {
-            Err(Error::InvalidArgument)
+    #[test]
+    fn test_that_the_retry_bits_of_the_status_byte_are_decoded() {
+        let ack = Ack::from_status_byte(0xa0, 5);
+
+        assert!(!ack.received);
+        assert!(!ack.power_detector);
+        assert_eq!(ack.retry, 10);
+        assert_eq!(ack.length, 5);
+        assert_eq!(ack.to_status_byte(), 0xa0);
+    }
+
+    #[test]
+    fn test_that_the_status_byte_round_trips() {
+        for status in (0..=0xff).filter(|status| status & 0x0c == 0) {
+            assert_eq!(Ack::from_status_byte(status, 0).to_status_byte(), status);
         }
     }
-}
 
-impl From<Channel> for u8 {
-    fn from(val: Channel) -> Self {
-        val.0
+    #[test]
+    fn test_that_sending_after_the_deadline_times_out() {
+        let mut cr = Crazyradio::from_transport(MockTransport {
+            ack: vec![0x01],
+            ..Default::default()
+        })
+        .unwrap();
+        let mut ack_data = [0u8; 32];
+
+        let late = cr.send_packet_deadline(&[0xff], &mut ack_data, Instant::now());
+        let in_time = cr.send_packet_deadline(
+            &[0xff],
+            &mut ack_data,
+            Instant::now() + Duration::from_secs(1),
+        );
+
+        assert!(matches!(late, Err(Error::Timeout)));
+        assert!(in_time.is_ok());
     }
-}
 
-/// Radio datarate
-#[derive(Copy, Clone, PartialEq)]
-pub enum Datarate {
-    Dr250K = 0,
-    Dr1M = 1,
-    Dr2M = 2,
-}
+    #[test]
+    #[cfg(feature = "rusb")]
+    fn test_that_only_duplicated_serials_are_reported() {
+        let serials = ["A", "B", "A", "C", "B", "A"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        assert_eq!(
+            duplicated_serials(serials),
+            vec![("A".to_string(), 3), ("B".to_string(), 2)]
+        );
+    }
 
-/// Radio power
-pub enum Power {
-    Pm18dBm = 0,
-    Pm12dBm = 1,
-    Pm6dBm = 2,
-    P0dBm = 3,
-}
+    #[test]
+    fn test_that_ard_in_microseconds_must_be_a_step() {
+        let mut cr = Crazyradio::from_transport(MockTransport::default()).unwrap();
+        cr.transport.control_requests.clear();
 
-#[cfg(test)]
-mod tests {
-    #[cfg(feature = "serde_support")]
-    use serde_json;
+        cr.set_ard_us(250).unwrap();
+        cr.set_ard_us(4000).unwrap();
 
-    #[cfg(feature = "serde_support")]
-    use super::Channel;
+        assert_eq!(cr.transport.control_requests, vec![(0x05, 0), (0x05, 15)]);
+        assert!(cr.set_ard_us(0).is_err());
+        assert!(cr.set_ard_us(300).is_err());
+        assert!(cr.set_ard_us(4250).is_err());
+    }
+
+    #[test]
+    fn test_that_ard_time_boundaries_map_to_the_first_and_last_steps() {
+        let mut cr = Crazyradio::from_transport(MockTransport::default()).unwrap();
+        cr.transport.control_requests.clear();
+
+        cr.set_ard_time(Duration::from_micros(250)).unwrap();
+        cr.set_ard_time(Duration::from_micros(4000)).unwrap();
+        cr.set_ard_time(Duration::from_micros(251)).unwrap();
+
+        assert_eq!(
+            cr.transport.control_requests,
+            vec![(0x05, 0), (0x05, 15), (0x05, 1)]
+        );
+        assert!(matches!(
+            cr.set_ard_time(Duration::from_micros(4001)),
+            Err(Error::InvalidArgument)
+        ));
+        assert!(matches!(
+            cr.set_ard_time(Duration::from_millis(4000)),
+            Err(Error::InvalidArgument)
+        ));
+    }
+
+    #[test]
+    fn test_that_scan_all_prefers_the_fastest_datarate_on_equality() {
+        let mut cr = Crazyradio::from_transport(MockTransport {
+            ack: vec![0x21],
+            ..Default::default()
+        })
+        .unwrap();
+        let range = ChannelRange::new(Channel(10), Channel(11)).unwrap();
+
+        let found = cr.scan_all(range, &[0xff]).unwrap();
+
+        assert_eq!(
+            found,
+            vec![
+                ScanFound {
+                    channel: Channel(10),
+                    datarate: Datarate::Dr2M,
+                    retry: 2
+                },
+                ScanFound {
+                    channel: Channel(11),
+                    datarate: Datarate::Dr2M,
+                    retry: 2
+                },
+            ]
+        );
+        assert_eq!(cr.channel(), Channel(2));
+    }
+
+    #[test]
+    fn test_that_ack_timeout_sets_the_ard_from_a_time_or_a_length() {
+        let mut cr = Crazyradio::from_transport(MockTransport::default()).unwrap();
+        cr.transport.control_requests.clear();
+
+        cr.set_ack_timeout(AckTimeout::Time(Duration::from_micros(500)))
+            .unwrap();
+        cr.set_ack_timeout(AckTimeout::Bytes(10)).unwrap();
+
+        assert_eq!(
+            cr.transport.control_requests,
+            vec![(0x05, 1), (0x05, 0x80 | 10)]
+        );
+    }
+
+    #[test]
+    fn test_that_cache_hits_and_misses_are_counted() {
+        let mut cr = Crazyradio::from_transport(MockTransport::default()).unwrap();
+        cr.reset_cache_stats();
+
+        cr.set_channel(Channel(2)).unwrap();
+        cr.set_channel(Channel(80)).unwrap();
+        cr.set_datarate(Datarate::Dr2M).unwrap();
+
+        let stats = cr.cache_stats();
+        assert_eq!(stats.channel_hits, 1);
+        assert_eq!(stats.channel_misses, 1);
+        assert_eq!(stats.datarate_hits, 1);
+        assert_eq!(stats.datarate_misses, 0);
+    }
+
+    #[test]
+    fn test_that_link_stats_loss_ratio_is_computed_from_sent_and_acked() {
+        let stats = LinkStats {
+            sent: 4,
+            acked: 3,
+            ..Default::default()
+        };
+
+        assert_eq!(stats.loss_ratio(), 0.25);
+        assert_eq!(LinkStats::default().loss_ratio(), 0.0);
+    }
 
     #[test]
     #[cfg(feature = "serde_support")]
@@ -618,6 +3922,7 @@ mod tests {
 
     #[test]
     #[cfg(feature = "serde_support")]
+    #[allow(clippy::redundant_pattern_matching)]
     fn test_that_deserializing_an_incorrect_channel_works() {
         let test_str = "126";
 
@@ -635,4 +3940,46 @@ mod tests {
 
         assert!(matches!(result, Ok(str) if str == "42"));
     }
+
+    #[test]
+    #[cfg(feature = "serde_support")]
+    fn test_that_channel_range_serialization_round_trips() {
+        let range = ChannelRange::new(Channel(0), Channel(125)).unwrap();
+
+        let serialized = serde_json::to_string(&range).unwrap();
+        let result: Result<ChannelRange, serde_json::Error> = serde_json::from_str(&serialized);
+
+        assert_eq!(serialized, r#"{"start":0,"stop":125}"#);
+        assert!(matches!(result, Ok(r) if r == range));
+    }
+
+    #[test]
+    #[cfg(feature = "serde_support")]
+    fn test_that_radio_config_serialization_round_trips() {
+        let config = RadioConfig {
+            channel: Channel(80),
+            datarate: Datarate::Dr250K,
+            address: [1, 2, 3, 4, 5],
+            power: Power::Pm6dBm,
+        };
+
+        let serialized = serde_json::to_string(&config).unwrap();
+        let result: RadioConfig = serde_json::from_str(&serialized).unwrap();
+
+        assert_eq!(
+            serialized,
+            r#"{"channel":80,"datarate":"Dr250K","address":[1,2,3,4,5],"power":"Pm6dBm"}"#
+        );
+        assert_eq!(result, config);
+    }
+
+    #[test]
+    #[cfg(feature = "serde_support")]
+    fn test_that_deserializing_an_inverted_channel_range_fails() {
+        let test_str = r#"{"start":80,"stop":2}"#;
+
+        let result: Result<ChannelRange, serde_json::Error> = serde_json::from_str(test_str);
+
+        assert!(result.is_err());
+    }
 }
@@ -2,7 +2,9 @@
 #![cfg_attr(docsrs, doc(cfg(feature = "shared_radio")))]
 
 use crate::Result;
-use crate::{Ack, Channel, Crazyradio};
+use crate::{Ack, Channel, Crazyradio, Error};
+#[cfg(feature = "async")]
+use crate::{Backoff, RadioConfig};
 use flume::{bounded, unbounded, Receiver, Sender};
 
 /// Multi-user threaded Crazyradio
@@ -44,8 +46,7 @@ pub struct SharedCrazyradio {
 }
 
 impl SharedCrazyradio {
-
-    /// Create a shared crazyradio. The Shared Crazyradio takes ownership of the 
+    /// Create a shared crazyradio. The Shared Crazyradio takes ownership of the
     /// Crazyradio object to that it is not usable outside anymore.
     ///
     /// Will spawn a thread that service the radio requests. The radio can be
@@ -123,21 +124,68 @@ impl SharedCrazyradio {
 
         let result = self.send_packet_res.recv().unwrap()?;
 
-        Ok((
-            Ack {
-                received: result.acked,
-                length: result.payload.len(),
-                power_detector: false,
-                retry: 0,
-            },
-            result.payload,
-        ))
+        Ok((result.ack, result.payload))
     }
 }
 
 #[cfg(feature = "async")]
 #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
 impl SharedCrazyradio {
+    /// Open the first Crazyradio detected and share it
+    ///
+    /// Same as calling [Crazyradio::open_first_async()] and [SharedCrazyradio::new()].
+    pub async fn open_first_async() -> Result<Self> {
+        Ok(Self::new(Crazyradio::open_first_async().await?))
+    }
+
+    /// Open the nth Crazyradio detected and share it
+    ///
+    /// Same as calling [Crazyradio::open_nth_async()] and [SharedCrazyradio::new()].
+    pub async fn open_nth_async(nth: usize) -> Result<Self> {
+        Ok(Self::new(Crazyradio::open_nth_async(nth).await?))
+    }
+
+    /// Open a Crazyradio by serial number and share it
+    ///
+    /// Same as calling [Crazyradio::open_by_serial_async()] and [SharedCrazyradio::new()].
+    pub async fn open_by_serial_async(serial: &str) -> Result<Self> {
+        Ok(Self::new(Crazyradio::open_by_serial_async(serial).await?))
+    }
+
+    /// Reconnect the shared radio after it has been disconnected
+    ///
+    /// The radio is closed and reopened by serial number using
+    /// [Crazyradio::reconnect_async()], then `config` is applied. Until the
+    /// radio is reopened, the packets sent and scans return
+    /// [Error::Disconnected]. All the clones of this object use the
+    /// reconnected radio.
+    ///
+    /// If the radio cannot be reopened the error is returned and the shared
+    /// radio stays disconnected, this function can be called again to retry.
+    ///
+    /// Returns [Error::NotFound], without closing the radio, if its serial
+    /// number could not be read when this object was created.
+    pub async fn reconnect_async(&self, config: &RadioConfig, backoff: Backoff) -> Result<()> {
+        // The radio thread answers once the USB device is released
+        let (client, closed) = bounded(1);
+        self.radio_command
+            .send_async(RadioCommand::Close { client })
+            .await
+            .unwrap();
+        let serial = closed.recv_async().await.unwrap().ok_or(Error::NotFound)?;
+
+        let radio = Crazyradio::reconnect_async(&serial, config, backoff).await?;
+
+        self.radio_command
+            .send_async(RadioCommand::Open {
+                radio: Box::new(radio),
+            })
+            .await
+            .unwrap();
+
+        Ok(())
+    }
+
     /// Async version of `scan()`
     pub async fn scan_async(
         &self,
@@ -181,15 +229,35 @@ impl SharedCrazyradio {
 
         let result = self.send_packet_res.recv_async().await.unwrap()?;
 
-        Ok((
-            Ack {
-                received: result.acked,
-                length: result.payload.len(),
-                power_detector: false,
-                retry: 0,
-            },
-            result.payload,
-        ))
+        Ok((result.ack, result.payload))
+    }
+}
+
+impl Crazyradio {
+    /// Move the radio to a dedicated thread and return a handle to it
+    ///
+    /// Same as calling [SharedCrazyradio::new()]. The [SharedCrazyradio]
+    /// does not use locks: the radio is owned by its thread and all the
+    /// clones of the handle send commands to this thread through a channel,
+    /// each one waiting for its own response. The accesses to the radio are
+    /// thus serialized, from sync and async code alike.
+    pub fn into_actor(self) -> SharedCrazyradio {
+        SharedCrazyradio::new(self)
+    }
+
+    /// Open all the connected Crazyradios, each in its own thread
+    ///
+    /// Each radio is opened, reset to boot values, and moved to a dedicated
+    /// thread with [Crazyradio::into_actor()]. The [SharedCrazyradio] handles
+    /// returned are `Send` and can be moved to worker threads or tasks. They
+    /// are in the same order as accepted by [Crazyradio::open_nth()].
+    ///
+    /// Fails if any of the radios cannot be opened, the radios already
+    /// opened are then closed.
+    pub fn open_all_for_workers() -> Result<Vec<SharedCrazyradio>> {
+        Crazyradio::iter()
+            .map(|radio| radio.map(Crazyradio::into_actor))
+            .collect()
     }
 }
 
@@ -213,6 +281,11 @@ impl Clone for SharedCrazyradio {
 }
 
 enum RadioCommand {
+    // Answered with the serial number of the radio, None if unknown and the radio is not closed
+    #[cfg(feature = "async")]
+    Close { client: Sender<Option<String>> },
+    #[cfg(feature = "async")]
+    Open { radio: Box<Crazyradio> },
     SendPacket {
         client: Sender<Result<SendPacketResult>>,
         channel: Channel,
@@ -229,7 +302,7 @@ enum RadioCommand {
 }
 
 struct SendPacketResult {
-    acked: bool,
+    ack: Ack,
     payload: Vec<u8>,
 }
 struct ScanResult {
@@ -243,7 +316,7 @@ fn scan(
     address: [u8; 5],
     payload: Vec<u8>,
 ) -> Result<ScanResult> {
-    crazyradio.set_address(&address)?;
+    crazyradio.set_address(address)?;
     let found = crazyradio.scan_channels(start, stop, &payload)?;
 
     Ok(ScanResult { found })
@@ -255,24 +328,38 @@ fn send_packet(
     address: [u8; 5],
     payload: Vec<u8>,
 ) -> Result<SendPacketResult> {
-    let mut ack_data = Vec::new();
-    ack_data.resize(32, 0);
+    let mut ack_data = vec![0; Crazyradio::MAX_ACK_LEN];
     crazyradio.set_channel(channel)?;
-    crazyradio.set_address(&address)?;
+    crazyradio.set_address(address)?;
 
     let ack = crazyradio.send_packet(&payload, &mut ack_data)?;
     ack_data.resize(ack.length, 0);
 
     Ok(SendPacketResult {
-        acked: ack.received,
+        ack,
         payload: ack_data,
     })
 }
 
 fn radio_loop(crazyradio: Crazyradio, radio_cmd: Receiver<RadioCommand>) {
-    let mut crazyradio = crazyradio;
+    // Read in this thread so that SharedCrazyradio::new() does not wait for it
+    #[cfg(feature = "async")]
+    let serial = crazyradio.serial().ok();
+    // None while the radio is closed to be reconnected
+    let mut crazyradio = Some(crazyradio);
     for command in radio_cmd {
         match command {
+            #[cfg(feature = "async")]
+            RadioCommand::Close { client } => {
+                // The radio could not be reopened without its serial number
+                if serial.is_some() {
+                    crazyradio = None;
+                }
+                // Ignore the error if the client has dropped since it did the request
+                let _ = client.send(serial.clone());
+            }
+            #[cfg(feature = "async")]
+            RadioCommand::Open { radio } => crazyradio = Some(*radio),
             RadioCommand::Scan {
                 client,
                 start,
@@ -280,7 +367,10 @@ fn radio_loop(crazyradio: Crazyradio, radio_cmd: Receiver<RadioCommand>) {
                 address,
                 payload,
             } => {
-                let res = scan(&mut crazyradio, start, stop, address, payload);
+                let res = match crazyradio.as_mut() {
+                    Some(crazyradio) => scan(crazyradio, start, stop, address, payload),
+                    None => Err(Error::Disconnected),
+                };
                 // Ignore the error if the client has dropped since it did the request
                 let _ = client.send(res);
             }
@@ -290,7 +380,10 @@ fn radio_loop(crazyradio: Crazyradio, radio_cmd: Receiver<RadioCommand>) {
                 address,
                 payload,
             } => {
-                let res = send_packet(&mut crazyradio, channel, address, payload);
+                let res = match crazyradio.as_mut() {
+                    Some(crazyradio) => send_packet(crazyradio, channel, address, payload),
+                    None => Err(Error::Disconnected),
+                };
                 // Ignore the error if the client has dropped since it did the request
                 let _ = client.send(res);
             }
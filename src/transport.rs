@@ -0,0 +1,239 @@
+use crate::Error;
+#[cfg(feature = "rusb")]
+use crate::{get_serial, Result};
+use core::time::Duration;
+
+/// USB transport used to communicate with a Crazyradio
+///
+/// The [Crazyradio](crate::Crazyradio) protocol logic (command encoding, ack
+/// parsing, retry settings and channel validation) only needs three USB
+/// operations: vendor OUT control transfers and bulk transfers on the
+/// endpoints 0x01 (OUT) and 0x81 (IN). Implementing this trait allows to use
+/// the Crazyradio with another USB host stack than rusb, see
+/// [Crazyradio::from_transport()](crate::Crazyradio::from_transport()).
+///
+/// [RusbTransport] is the implementation used by default, it is only available
+/// with the **rusb** feature. The other host stack still has to run on a
+/// platform with `std`, the crate does not support `no_std`.
+pub trait UsbTransport {
+    /// Error returned by the transfers
+    ///
+    /// It is converted to the crate [Error] by the [Crazyradio](crate::Crazyradio)
+    /// functions. A transfer that times out should be reported as
+    /// [Error::Timeout], a device that is gone as [Error::Disconnected] and
+    /// other failures as [Error::Transport].
+    type Error: Into<Error>;
+
+    /// Send a vendor OUT control transfer (request type 0x40) to the device
+    ///
+    /// Returns the number of bytes of `data` sent.
+    fn control_out(
+        &mut self,
+        request: u8,
+        value: u16,
+        index: u16,
+        data: &[u8],
+        timeout: Duration,
+    ) -> core::result::Result<usize, Self::Error>;
+
+    /// Write `data` to the bulk OUT `endpoint`
+    ///
+    /// Returns the number of bytes written.
+    fn bulk_out(
+        &mut self,
+        endpoint: u8,
+        data: &[u8],
+        timeout: Duration,
+    ) -> core::result::Result<usize, Self::Error>;
+
+    /// Read from the bulk IN `endpoint` into `buffer`
+    ///
+    /// Returns the number of bytes read.
+    fn bulk_in(
+        &mut self,
+        endpoint: u8,
+        buffer: &mut [u8],
+        timeout: Duration,
+    ) -> core::result::Result<usize, Self::Error>;
+}
+
+/// rusb implementation of [UsbTransport]
+///
+/// Created by the [Crazyradio](crate::Crazyradio) open functions.
+#[cfg(feature = "rusb")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rusb")))]
+pub struct RusbTransport {
+    pub(crate) device_desciptor: rusb::DeviceDescriptor,
+    pub(crate) device_handle: rusb::DeviceHandle<rusb::GlobalContext>,
+    kernel_driver_detached: bool,
+}
+
+#[cfg(feature = "rusb")]
+impl RusbTransport {
+    // Open a device and claim the Crazyradio interface
+    pub(crate) fn open(
+        device: rusb::Device<rusb::GlobalContext>,
+        detach_kernel_driver: bool,
+    ) -> Result<Self> {
+        let device_desciptor = device.device_descriptor()?;
+        let device_handle = device.open()?;
+
+        // kernel_driver_active() returns an error on platforms without kernel driver support
+        let kernel_driver_detached =
+            detach_kernel_driver && device_handle.kernel_driver_active(0).unwrap_or(false);
+        if kernel_driver_detached {
+            device_handle.detach_kernel_driver(0)?;
+        }
+
+        // Built before claiming so that the driver is reattached if the claim fails
+        let transport = RusbTransport {
+            device_desciptor,
+            device_handle,
+            kernel_driver_detached,
+        };
+
+        transport.device_handle.claim_interface(0)?;
+
+        Ok(transport)
+    }
+
+    pub(crate) fn serial(&self) -> Result<String> {
+        get_serial(&self.device_desciptor, &self.device_handle)
+    }
+
+    pub(crate) fn manufacturer(&self) -> Result<String> {
+        let languages = self.device_handle.read_languages(Duration::from_secs(1))?;
+        let language = languages.first().ok_or(Error::NotFound)?;
+
+        Ok(self.device_handle.read_manufacturer_string(
+            *language,
+            &self.device_desciptor,
+            Duration::from_secs(1),
+        )?)
+    }
+
+    pub(crate) fn product(&self) -> Result<String> {
+        let languages = self.device_handle.read_languages(Duration::from_secs(1))?;
+        let language = languages.first().ok_or(Error::NotFound)?;
+
+        Ok(self.device_handle.read_product_string(
+            *language,
+            &self.device_desciptor,
+            Duration::from_secs(1),
+        )?)
+    }
+}
+
+#[cfg(feature = "rusb")]
+impl Drop for RusbTransport {
+    fn drop(&mut self) {
+        if self.kernel_driver_detached {
+            // The interface has to be released before the kernel driver can bind to it again
+            let _ = self.device_handle.release_interface(0);
+            let _ = self.device_handle.attach_kernel_driver(0);
+        }
+    }
+}
+
+#[cfg(feature = "rusb")]
+impl UsbTransport for RusbTransport {
+    type Error = rusb::Error;
+
+    fn control_out(
+        &mut self,
+        request: u8,
+        value: u16,
+        index: u16,
+        data: &[u8],
+        timeout: Duration,
+    ) -> rusb::Result<usize> {
+        self.device_handle
+            .write_control(0x40, request, value, index, data, timeout)
+    }
+
+    fn bulk_out(&mut self, endpoint: u8, data: &[u8], timeout: Duration) -> rusb::Result<usize> {
+        self.device_handle.write_bulk(endpoint, data, timeout)
+    }
+
+    fn bulk_in(
+        &mut self,
+        endpoint: u8,
+        buffer: &mut [u8],
+        timeout: Duration,
+    ) -> rusb::Result<usize> {
+        self.device_handle.read_bulk(endpoint, buffer, timeout)
+    }
+}
+
+/// Transport recording the packets sent instead of communicating with a dongle
+///
+/// Intended to test code using a [Crazyradio](crate::Crazyradio) without
+/// dongle. Control requests are accepted and ignored, the packets sent are
+/// recorded and acked with an empty ack payload.
+///
+/// Example:
+/// ```
+/// use crazyradio::{Crazyradio, DryRunTransport};
+/// # fn main() -> Result<(), crazyradio::Error> {
+/// let mut cr = Crazyradio::from_transport(DryRunTransport::new())?;
+///
+/// let mut ack_data = [0u8; 32];
+/// let ack = cr.send_packet(&[0x01, 0x02], &mut ack_data)?;
+///
+/// assert!(ack.received);
+/// assert_eq!(cr.transport().packets(), &[vec![0x01, 0x02]]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DryRunTransport {
+    packets: Vec<Vec<u8>>,
+}
+
+impl DryRunTransport {
+    /// Create a transport without any recorded packet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Packets sent, in order
+    pub fn packets(&self) -> &[Vec<u8>] {
+        &self.packets
+    }
+
+    /// Forget the packets recorded so far
+    pub fn clear(&mut self) {
+        self.packets.clear();
+    }
+}
+
+impl UsbTransport for DryRunTransport {
+    type Error = Error;
+
+    fn control_out(
+        &mut self,
+        _request: u8,
+        _value: u16,
+        _index: u16,
+        data: &[u8],
+        _timeout: Duration,
+    ) -> crate::Result<usize> {
+        Ok(data.len())
+    }
+
+    fn bulk_out(&mut self, _endpoint: u8, data: &[u8], _timeout: Duration) -> crate::Result<usize> {
+        self.packets.push(data.to_vec());
+        Ok(data.len())
+    }
+
+    fn bulk_in(
+        &mut self,
+        _endpoint: u8,
+        buffer: &mut [u8],
+        _timeout: Duration,
+    ) -> crate::Result<usize> {
+        // Status byte with only the ack received bit set, no ack payload
+        buffer[0] = 0x01;
+        Ok(1)
+    }
+}
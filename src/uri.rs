@@ -0,0 +1,373 @@
+use crate::{Channel, Datarate, Error, Result};
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Crazyflie radio URI
+///
+/// Radio links are described in the Crazyflie ecosystem by URIs of the form
+/// `radio://<dongle>/<channel>/<datarate>[/<address>]`, for example
+/// `radio://0/80/2M/E7E7E7E7E7`:
+///  - **dongle** is the index of the Crazyradio, as accepted by [Crazyradio::open_nth()](crate::Crazyradio::open_nth())
+///  - **channel** is the radio channel number, from 0 to 125
+///  - **datarate** is one of `250K`, `1M` or `2M`
+///  - **address** is the optional radio address written as 10 hexadecimal digits.
+///    The bytes are written in the same order as passed to
+///    [Crazyradio::set_address()](crate::Crazyradio::set_address()). It defaults to `E7E7E7E7E7`.
+///
+/// Query parameters, like `?safelink=0`, are ignored.
+///
+/// Example:
+/// ```
+/// use crazyradio::{Channel, Datarate, RadioUri};
+/// # fn main() -> Result<(), crazyradio::Error> {
+/// let uri: RadioUri = "radio://0/80/2M/E7E7E7E706".parse()?;
+///
+/// assert_eq!(uri.channel(), Channel::from_number(80)?);
+/// assert_eq!(uri.datarate(), Datarate::Dr2M);
+/// assert_eq!(uri.address(), [0xe7, 0xe7, 0xe7, 0xe7, 0x06]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RadioUri {
+    dongle_index: usize,
+    channel: Channel,
+    datarate: Datarate,
+    address: [u8; 5],
+}
+
+impl RadioUri {
+    /// Index of the Crazyradio, as accepted by [Crazyradio::open_nth()](crate::Crazyradio::open_nth())
+    pub fn dongle_index(&self) -> usize {
+        self.dongle_index
+    }
+
+    /// Radio channel
+    pub fn channel(&self) -> Channel {
+        self.channel
+    }
+
+    /// Radio datarate
+    pub fn datarate(&self) -> Datarate {
+        self.datarate
+    }
+
+    /// Radio address
+    pub fn address(&self) -> [u8; 5] {
+        self.address
+    }
+}
+
+impl FromStr for RadioUri {
+    type Err = Error;
+
+    fn from_str(uri: &str) -> Result<Self> {
+        let path = uri.strip_prefix("radio://").ok_or(Error::InvalidArgument)?;
+        let path = path.split('?').next().unwrap_or_default();
+
+        let fields: Vec<&str> = path.split('/').collect();
+        if fields.len() != 3 && fields.len() != 4 {
+            return Err(Error::InvalidArgument);
+        }
+
+        let dongle_index = fields[0]
+            .parse::<usize>()
+            .map_err(|_| Error::InvalidArgument)?;
+
+        let channel = fields[1]
+            .parse::<u8>()
+            .map_err(|_| Error::InvalidArgument)?;
+        let channel = Channel::from_number(channel)?;
+
+        let datarate = match fields[2] {
+            "250K" => Datarate::Dr250K,
+            "1M" => Datarate::Dr1M,
+            "2M" => Datarate::Dr2M,
+            _ => return Err(Error::InvalidArgument),
+        };
+
+        let address = match fields.get(3) {
+            Some(address) => address_from_hex(address)?,
+            None => [0xe7; 5],
+        };
+
+        Ok(RadioUri {
+            dongle_index,
+            channel,
+            datarate,
+            address,
+        })
+    }
+}
+
+/// Radio link: channel, datarate and address used to reach a device
+///
+/// The link is written as a radio URI (see [RadioUri]). The dongle index is
+/// not part of the link: it is ignored when parsing and written as `0`, and a
+/// [RadioUri] converted from a link has the dongle index 0.
+/// With the **serde_support** feature, the link is serialized as its URI
+/// string, which allows to write it this way in configuration files:
+/// ```json
+/// { "uri": "radio://0/80/2M/E7E7E7E706" }
+/// ```
+///
+/// Example:
+/// ```no_run
+/// use crazyradio::{Crazyradio, RadioLink};
+/// # #[cfg(feature = "rusb")]
+/// # fn main() -> Result<(), crazyradio::Error> {
+/// let link: RadioLink = "radio://0/80/2M/E7E7E7E706".parse()?;
+///
+/// let mut cr = Crazyradio::open_first()?;
+/// cr.configure_from_uri(&link.into())?;
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "rusb"))]
+/// # fn main() {}
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RadioLink {
+    channel: Channel,
+    datarate: Datarate,
+    address: [u8; 5],
+}
+
+impl RadioLink {
+    /// Create a link from its channel, datarate and address
+    pub fn new(channel: Channel, datarate: Datarate, address: [u8; 5]) -> Self {
+        RadioLink {
+            channel,
+            datarate,
+            address,
+        }
+    }
+
+    /// Radio channel
+    pub fn channel(&self) -> Channel {
+        self.channel
+    }
+
+    /// Radio datarate
+    pub fn datarate(&self) -> Datarate {
+        self.datarate
+    }
+
+    /// Radio address
+    pub fn address(&self) -> [u8; 5] {
+        self.address
+    }
+}
+
+impl From<RadioUri> for RadioLink {
+    fn from(uri: RadioUri) -> Self {
+        RadioLink::new(uri.channel, uri.datarate, uri.address)
+    }
+}
+
+impl From<RadioLink> for RadioUri {
+    fn from(link: RadioLink) -> Self {
+        RadioUri {
+            dongle_index: 0,
+            channel: link.channel,
+            datarate: link.datarate,
+            address: link.address,
+        }
+    }
+}
+
+impl FromStr for RadioLink {
+    type Err = Error;
+
+    fn from_str(uri: &str) -> Result<Self> {
+        Ok(uri.parse::<RadioUri>()?.into())
+    }
+}
+
+impl fmt::Display for RadioLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let datarate = match self.datarate {
+            Datarate::Dr250K => "250K",
+            Datarate::Dr1M => "1M",
+            Datarate::Dr2M => "2M",
+        };
+
+        write!(
+            f,
+            "radio://0/{}/{}/{}",
+            self.channel.0,
+            datarate,
+            Address::from(self.address).to_uri_str()
+        )
+    }
+}
+
+#[cfg(feature = "serde_support")]
+impl Serialize for RadioLink {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde_support")]
+impl<'de> Deserialize<'de> for RadioLink {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<RadioLink, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let uri: String = Deserialize::deserialize(deserializer)?;
+        let link = uri
+            .parse()
+            .map_err(|e| serde::de::Error::custom(format!("{:?}", e)))?;
+        Ok(link)
+    }
+}
+
+/// Radio address
+///
+/// The address is made of 5 bytes. The Crazyflie ecosystem (URIs, cfclient)
+/// writes it as 10 hexadecimal digits where the first two digits are the
+/// first byte: `E7E7E7E706` is the address `[0xe7, 0xe7, 0xe7, 0xe7, 0x06]`.
+/// The bytes are passed in the same order to
+/// [Crazyradio::set_address()](crate::Crazyradio::set_address()), which
+/// accepts an [Address] as well as a `&[u8; 5]`.
+///
+/// Example:
+/// ```
+/// use crazyradio::Address;
+/// # fn main() -> Result<(), crazyradio::Error> {
+/// let address = Address::from_uri_str("E7E7E7E706")?;
+///
+/// assert_eq!(address.bytes(), &[0xe7, 0xe7, 0xe7, 0xe7, 0x06]);
+/// assert_eq!(address.to_uri_str(), "E7E7E7E706");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Address([u8; 5]);
+
+impl Address {
+    /// Parse an address written as 10 hexadecimal digits, like in a radio URI
+    ///
+    /// Returns [Error::InvalidArgument] if the string is not a valid address.
+    pub fn from_uri_str(address: &str) -> Result<Self> {
+        Ok(Address(address_from_hex(address)?))
+    }
+
+    /// Write the address as 10 uppercase hexadecimal digits, like in a radio URI
+    pub fn to_uri_str(&self) -> String {
+        self.0.iter().map(|byte| format!("{:02X}", byte)).collect()
+    }
+
+    /// Address bytes, in the order expected by the radio
+    pub fn bytes(&self) -> &[u8; 5] {
+        &self.0
+    }
+}
+
+impl From<[u8; 5]> for Address {
+    fn from(bytes: [u8; 5]) -> Self {
+        Address(bytes)
+    }
+}
+
+impl From<&[u8; 5]> for Address {
+    fn from(bytes: &[u8; 5]) -> Self {
+        Address(*bytes)
+    }
+}
+
+impl From<Address> for [u8; 5] {
+    fn from(address: Address) -> Self {
+        address.0
+    }
+}
+
+// Parse a 10 hexadecimal digits radio address, the first two digits being the first byte
+pub(crate) fn address_from_hex(hex: &str) -> Result<[u8; 5]> {
+    if hex.len() != 10 || !hex.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return Err(Error::InvalidArgument);
+    }
+
+    let mut address = [0u8; 5];
+    for (i, byte) in address.iter_mut().enumerate() {
+        *byte =
+            u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).map_err(|_| Error::InvalidArgument)?;
+    }
+
+    Ok(address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Address, RadioLink, RadioUri};
+    use crate::{Channel, Datarate};
+
+    #[test]
+    fn test_that_parsing_a_full_uri_works() {
+        let uri: RadioUri = "radio://3/80/250K/0102030405".parse().unwrap();
+
+        assert_eq!(uri.dongle_index(), 3);
+        assert_eq!(uri.channel(), Channel::from_number(80).unwrap());
+        assert_eq!(uri.datarate(), Datarate::Dr250K);
+        assert_eq!(uri.address(), [0x01, 0x02, 0x03, 0x04, 0x05]);
+    }
+
+    #[test]
+    fn test_that_parsing_a_uri_without_address_uses_the_default_address() {
+        let uri: RadioUri = "radio://1/2/1M?safelink=0".parse().unwrap();
+
+        assert_eq!(uri.channel(), Channel::from_number(2).unwrap());
+        assert_eq!(uri.datarate(), Datarate::Dr1M);
+        assert_eq!(uri.address(), [0xe7; 5]);
+    }
+
+    #[test]
+    fn test_that_parsing_an_invalid_uri_fails() {
+        let invalid = [
+            "usb://0",
+            "radio://0/126/2M",
+            "radio://0/80/3M",
+            "radio://x/80/2M",
+            "radio://0/80/2M/E7E7E7E7",
+            "radio://0/80/2M/E7E7E7E7G7",
+            "radio://0/80/2M/E7E7E7E7E7/1",
+        ];
+
+        for uri in invalid.iter() {
+            assert!(uri.parse::<RadioUri>().is_err(), "{} should not parse", uri);
+        }
+    }
+
+    #[test]
+    fn test_that_address_uri_string_round_trips() {
+        let address = Address::from_uri_str("0102030AFF").unwrap();
+
+        assert_eq!(address.bytes(), &[0x01, 0x02, 0x03, 0x0a, 0xff]);
+        assert_eq!(address.to_uri_str(), "0102030AFF");
+    }
+
+    #[test]
+    fn test_that_radio_link_is_written_as_a_uri() {
+        let link: RadioLink = "radio://1/80/250K/E7E7E7E706?safelink=0".parse().unwrap();
+
+        assert_eq!(link.to_string(), "radio://0/80/250K/E7E7E7E706");
+    }
+
+    #[test]
+    #[cfg(feature = "serde_support")]
+    fn test_that_radio_link_serialization_round_trips() {
+        let link: RadioLink = "radio://0/2/1M/0102030405".parse().unwrap();
+
+        let serialized = serde_json::to_string(&link).unwrap();
+        let result: RadioLink = serde_json::from_str(&serialized).unwrap();
+
+        assert_eq!(serialized, r#""radio://0/2/1M/0102030405""#);
+        assert_eq!(result, link);
+        assert!(serde_json::from_str::<RadioLink>(r#""radio://0/2/3M""#).is_err());
+    }
+}